@@ -0,0 +1,469 @@
+//! Optional control API for stream overlays and companion tools. Disabled by default and served only on 127.0.0.1.
+//! `POST /rpc` takes JSON-RPC 2.0 calls, `GET /events` streams every change written into saves as server-sent events.
+//! Every request needs the token from the config as `Authorization: Bearer <token>`. Event streams may pass it as
+//! `?token=<token>` instead, since browsers can't set headers of an `EventSource`. Web pages can read responses
+//! only if their origin is allowed in the config
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::io::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tiny_http::{Header, Method, Request, Response, Server};
+use crate::export::{self, SaveExport};
+use crate::journal::{Entry, Source};
+use crate::progress::Progress;
+use crate::tokenizer::{InfoData, SaveData};
+use crate::Game as FnafWorldGame;
+
+/// Comment sent to idle event streams, so closed connections are noticed
+const KEEP_ALIVE: Duration = Duration::from_secs(15);
+/// Threads answering requests
+const WORKERS: usize = 4;
+/// Event streams open at once, every one keeps its own thread
+const MAX_STREAMS: usize = 8;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ApiConfig {
+    pub enabled: bool,
+    pub port: u16,
+    /// Made when the API is enabled for the first time
+    pub token: String,
+    /// Web pages (like `http://localhost:3000`) allowed to read responses in a browser, none by default
+    pub allowed_origins: Vec<String>,
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        Self { enabled: false, port: 8642, token: String::new(), allowed_origins: Vec::new() }
+    }
+}
+
+/// Origins separated by commas or spaces
+pub fn parse_origins(s: &str) -> Vec<String> {
+    s.split([',', ' ']).map(|o| o.trim().trim_end_matches('/')).filter(|o| !o.is_empty()).map(str::to_owned).collect()
+}
+
+/// 32 random hex digits
+pub fn new_token() -> String {
+    let part = || {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u128(std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_nanos());
+        hasher.finish()
+    };
+    format!("{:016x}{:016x}", part(), part())
+}
+
+/// Save and trophies loaded in the editor. Calls for them change these instead of reading the files again
+pub struct Shared {
+    pub save: &'static Mutex<SaveData>,
+    pub info: &'static Mutex<InfoData>,
+    /// Called after the API wrote the slot (starting from 1) or trophies (none)
+    pub changed: Box<dyn Fn(FnafWorldGame, Option<u8>) + Send + Sync>,
+}
+
+/// Slot a call is about. Loaded one if neither is given, slot of the loaded game if only `slot` is
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Target {
+    game: Option<String>,
+    slot: Option<u8>,
+}
+
+#[derive(Deserialize)]
+struct FieldParams {
+    /// Raw key as written into the file, e.g. `tokens` or `3lv`
+    field: String,
+    /// None removes the key
+    value: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct IdParams {
+    id: u32,
+}
+
+#[derive(Deserialize)]
+struct PresetParams {
+    name: String,
+    /// Unlock members of the loadout the save doesn't own
+    #[serde(default = "yes")]
+    unlock: bool,
+}
+
+fn yes() -> bool {
+    true
+}
+
+impl Shared {
+    fn target(&self, params: &Value) -> anyhow::Result<(FnafWorldGame, u8)> {
+        let target = Target::deserialize(params).unwrap_or_default();
+        let (game, slot) = {
+            let save = self.save.lock();
+            (save.game(), save.slot())
+        };
+        Ok(match (target.game, target.slot) {
+            (Some(g), s) => (export::game_from_str(&g)?, s.unwrap_or(1)),
+            (None, s) => (game, s.unwrap_or(slot)),
+        })
+    }
+
+    fn read_save(&self, params: &Value) -> anyhow::Result<SaveData> {
+        let (game, slot) = self.target(params)?;
+        let save = self.save.lock();
+        if save.game() == game && save.slot() == slot {
+            return Ok(save.clone());
+        }
+        drop(save);
+        SaveData::read(game, slot)
+    }
+
+    fn read_info(&self, game: FnafWorldGame) -> anyhow::Result<InfoData> {
+        let info = self.info.lock();
+        if info.game() == game {
+            return Ok(info.clone());
+        }
+        drop(info);
+        InfoData::read(game)
+    }
+
+    /// Changes the slot and writes it, through the editor's copy if it's the loaded one
+    fn edit_save<T>(&self, params: &Value, f: impl FnOnce(&mut SaveData) -> anyhow::Result<T>) -> anyhow::Result<T> {
+        let (game, slot) = self.target(params)?;
+        let res = (|| {
+            let mut loaded = self.save.lock();
+            if loaded.game() == game && loaded.slot() == slot {
+                let res = f(&mut loaded)?;
+                loaded.save(Source::Api)?;
+                return Ok(res);
+            }
+            drop(loaded);
+            let mut save = SaveData::read(game, slot)?;
+            let res = f(&mut save)?;
+            save.save(Source::Api)?;
+            anyhow::Ok(res)
+        })()?;
+        (self.changed)(game, Some(slot));
+        Ok(res)
+    }
+
+    fn edit_info(&self, params: &Value, f: impl FnOnce(&mut InfoData) -> anyhow::Result<()>) -> anyhow::Result<()> {
+        let (game, _) = self.target(params)?;
+        (|| {
+            let mut loaded = self.info.lock();
+            if loaded.game() == game {
+                f(&mut loaded)?;
+                return loaded.save(Source::Api);
+            }
+            drop(loaded);
+            let mut info = InfoData::read(game)?;
+            f(&mut info)?;
+            info.save(Source::Api)
+        })()?;
+        (self.changed)(game, None);
+        Ok(())
+    }
+
+    /// Runs a call. Errors are sent back to the caller
+    fn dispatch(&self, method: &str, params: &Value) -> anyhow::Result<Value> {
+        Ok(match method {
+            "get_slot" => serde_json::to_value(SaveExport::new(&self.read_save(params)?, None))?,
+            "get_info" => serde_json::to_value(self.read_info(self.target(params)?.0)?)?,
+            "progress" => {
+                let save = self.read_save(params)?;
+                let info = self.read_info(save.game())?;
+                let progress = Progress::new(&save, Some(&info));
+                let categories = progress.categories.iter()
+                    .map(|c| json!({ "name": c.name, "done": c.done, "total": c.total }))
+                    .collect::<Vec<_>>();
+                json!({ "game": export::game_to_str(save.game()), "slot": save.slot(), "overall": progress.overall(), "summary": progress.summary(), "categories": categories })
+            },
+            "set_field" => {
+                let p = FieldParams::deserialize(params)?;
+                self.edit_save(params, |save| save.raw_set(&p.field, p.value))?;
+                Value::Null
+            },
+            "set_info_field" => {
+                let p = FieldParams::deserialize(params)?;
+                self.edit_info(params, |info| info.raw_set(&p.field, p.value))?;
+                Value::Null
+            },
+            "unlock_character" => {
+                let id = IdParams::deserialize(params)?.id;
+                self.edit_save(params, |save| {
+                    if !save.contains_character(id) {
+                        let ch = save.edit_character(id);
+                        ch.lvl = 1;
+                        ch.next = 100;
+                    }
+                    Ok(())
+                })?;
+                Value::Null
+            },
+            "unlock_chip" => {
+                let id = IdParams::deserialize(params)?.id;
+                self.edit_save(params, |save| Ok(save.chips.insert(id)))?;
+                Value::Null
+            },
+            "unlock_byte" => {
+                let id = IdParams::deserialize(params)?.id;
+                self.edit_save(params, |save| Ok(save.bytes.insert(id)))?;
+                Value::Null
+            },
+            "apply_preset" => {
+                let p = PresetParams::deserialize(params)?;
+                let Some(loadout) = crate::config::CONFIG.lock().loadouts.iter().find(|l| l.name == p.name).cloned() else {
+                    anyhow::bail!("No loadout named \"{}\"", p.name);
+                };
+                let report = self.edit_save(params, |save| Ok(loadout.apply(save, p.unlock)))?;
+                Value::String(report.summary())
+            },
+            _ => return Err(UnknownMethod(method.to_owned()).into()),
+        })
+    }
+}
+
+#[derive(Debug, derive_more::Display)]
+#[display("Unknown method \"{_0}\"")]
+struct UnknownMethod(String);
+
+impl std::error::Error for UnknownMethod {}
+
+#[derive(Deserialize)]
+struct Call {
+    #[serde(default)]
+    id: Value,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+/// JSON-RPC response to the request body
+fn respond_rpc(shared: &Shared, body: &str) -> Value {
+    let call = match serde_json::from_str::<Call>(body) {
+        Ok(call) => call,
+        Err(e) => return json!({ "jsonrpc": "2.0", "id": null, "error": { "code": -32700, "message": e.to_string() } }),
+    };
+    match shared.dispatch(&call.method, &call.params) {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": call.id, "result": result }),
+        Err(e) => {
+            let code = if e.is::<UnknownMethod>() { -32601 } else { -32000 };
+            json!({ "jsonrpc": "2.0", "id": call.id, "error": { "code": code, "message": format!("{e:#}") } })
+        },
+    }
+}
+
+static SUBSCRIBERS: Mutex<Vec<Sender<String>>> = Mutex::new(Vec::new());
+/// Event streams being written
+static STREAMS: AtomicUsize = AtomicUsize::new(0);
+
+/// Sends journal entries of a write to every event stream
+pub fn publish(entries: &[Entry]) {
+    let mut subscribers = SUBSCRIBERS.lock();
+    if subscribers.is_empty() {
+        return;
+    }
+    let Ok(data) = serde_json::to_string(entries) else { return };
+    subscribers.retain(|s| s.send(format!("event: change\ndata: {data}\n\n")).is_ok());
+}
+
+/// Writes events until the API is stopped or the client goes away
+fn stream_events(events: Receiver<String>, out: &mut impl Write) -> std::io::Result<()> {
+    loop {
+        match events.recv_timeout(KEEP_ALIVE) {
+            Ok(event) => out.write_all(event.as_bytes())?,
+            Err(RecvTimeoutError::Timeout) => out.write_all(b": keep-alive\n\n")?,
+            Err(RecvTimeoutError::Disconnected) => return Ok(()),
+        }
+        out.flush()?;
+    }
+}
+
+fn header(name: &str, value: &str) -> Header {
+    Header::from_bytes(name.as_bytes(), value.as_bytes()).expect("Valid header")
+}
+
+/// Whether the request carries the token in the header, or in the query for event streams
+fn authorized(url: &str, authorization: Option<&str>, token: &str) -> bool {
+    if token.is_empty() {
+        return false;
+    }
+    let in_query = match url.split_once('?') {
+        Some(("/events", query)) => query.split('&').any(|p| p.strip_prefix("token=") == Some(token)),
+        _ => false,
+    };
+    in_query || authorization.and_then(|a| a.strip_prefix("Bearer ")) == Some(token)
+}
+
+/// Adds headers letting the page read the response if its origin is allowed
+fn with_cors<R: std::io::Read>(mut response: Response<R>, origin: Option<&str>) -> Response<R> {
+    if let Some(origin) = origin {
+        response.add_header(header("Access-Control-Allow-Origin", origin));
+        response.add_header(header("Vary", "Origin"));
+    }
+    response
+}
+
+fn handle(mut request: Request, shared: &Shared, config: &ApiConfig) -> std::io::Result<()> {
+    let origin = request.headers().iter()
+        .find(|h| h.field.equiv("Origin"))
+        .map(|h| h.value.to_string())
+        .filter(|o| config.allowed_origins.iter().any(|a| a == o));
+    let origin = origin.as_deref();
+    if *request.method() == Method::Options {
+        let mut response = with_cors(Response::empty(204), origin);
+        if origin.is_some() {
+            response.add_header(header("Access-Control-Allow-Headers", "Authorization, Content-Type"));
+            response.add_header(header("Access-Control-Allow-Methods", "GET, POST"));
+        }
+        return request.respond(response);
+    }
+    let authorization = request.headers().iter().find(|h| h.field.equiv("Authorization")).map(|h| h.value.to_string());
+    if !authorized(request.url(), authorization.as_deref(), &config.token) {
+        return request.respond(with_cors(Response::from_string("Wrong or missing token").with_status_code(401), origin));
+    }
+    let path = request.url().split('?').next().unwrap_or_default().to_owned();
+    match (request.method(), path.as_str()) {
+        (Method::Post, "/rpc") => {
+            let mut body = String::new();
+            request.as_reader().read_to_string(&mut body)?;
+            let response = Response::from_string(respond_rpc(shared, &body).to_string())
+                .with_header(header("Content-Type", "application/json"));
+            request.respond(with_cors(response, origin))
+        },
+        (Method::Get, "/events") => {
+            if STREAMS.fetch_add(1, Ordering::AcqRel) >= MAX_STREAMS {
+                STREAMS.fetch_sub(1, Ordering::AcqRel);
+                return request.respond(with_cors(Response::from_string("Too many event streams").with_status_code(503), origin));
+            }
+            let (tx, rx) = mpsc::channel();
+            SUBSCRIBERS.lock().push(tx);
+            let cors = origin.map(|o| format!("Access-Control-Allow-Origin: {o}\r\nVary: Origin\r\n")).unwrap_or_default();
+            // Responses of tiny_http are buffered, events have to be written one by one
+            let mut out = request.into_writer();
+            // Streams last long, so they don't keep the workers busy
+            std::thread::spawn(move || {
+                let head = format!("HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\n{cors}\r\n: connected\n\n");
+                let res = out.write_all(head.as_bytes()).and_then(|_| out.flush()).and_then(|_| stream_events(rx, &mut out));
+                STREAMS.fetch_sub(1, Ordering::AcqRel);
+                if let Err(e) = res {
+                    eprintln!("Event stream closed: {e}");
+                }
+            });
+            Ok(())
+        },
+        _ => request.respond(with_cors(Response::from_string("Not found").with_status_code(404), origin)),
+    }
+}
+
+struct Running {
+    port: u16,
+    server: Arc<Server>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl Running {
+    /// Lets the workers out and waits for them, so the returned server is the only handle left
+    fn stop_workers(self) -> (u16, Arc<Server>) {
+        // Every call lets one worker out
+        self.workers.iter().for_each(|_| self.server.unblock());
+        for worker in self.workers {
+            let _ = worker.join();
+        }
+        (self.port, self.server)
+    }
+}
+
+static RUNNING: Mutex<Option<Running>> = Mutex::new(None);
+
+/// (Re)starts the server with the config and closes event streams. Only stops it if the API is disabled
+pub fn start(config: &ApiConfig, shared: Arc<Shared>) -> anyhow::Result<()> {
+    let old = RUNNING.lock().take().map(Running::stop_workers);
+    SUBSCRIBERS.lock().clear();
+    if !config.enabled {
+        return Ok(());
+    }
+    // Same port keeps the listener, binding it again may fail until the old one is closed
+    let server = match old {
+        Some((port, server)) if port == config.port => server,
+        old => {
+            drop(old);
+            let server = Server::http(("127.0.0.1", config.port))
+                .map_err(|e| anyhow::anyhow!("Can't start the API on port {}: {e}", config.port))?;
+            Arc::new(server)
+        },
+    };
+    let config = Arc::new(config.clone());
+    let workers = (0..WORKERS)
+        .map(|_| {
+            let (server, shared, config) = (server.clone(), shared.clone(), config.clone());
+            std::thread::spawn(move || {
+                for request in server.incoming_requests() {
+                    if let Err(e) = handle(request, &shared, &config) {
+                        eprintln!("API request failed: {e}");
+                    }
+                }
+            })
+        })
+        .collect();
+    *RUNNING.lock() = Some(Running { port: config.port, server, workers });
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tokenizer::test_save;
+
+    #[test]
+    fn checks_token() {
+        assert!(authorized("/rpc", Some("Bearer abc"), "abc"));
+        assert!(authorized("/events?x=1&token=abc", None, "abc"));
+        assert!(!authorized("/rpc?token=abc", None, "abc"));
+        assert!(!authorized("/events?token=abcd", Some("Bearer ab"), "abc"));
+        assert!(!authorized("/rpc?token=", Some("Bearer "), ""));
+        assert_eq!(new_token().len(), 32);
+        assert_ne!(new_token(), new_token());
+    }
+
+    #[test]
+    fn parses_origins() {
+        assert_eq!(parse_origins("http://localhost:3000/, https://example.org  "), ["http://localhost:3000", "https://example.org"]);
+        assert!(parse_origins(" , ").is_empty());
+    }
+
+    #[test]
+    fn answers_read_calls() {
+        let save = test_save("[fnafw]\n1have=1\n1lv=4\ntokens=5");
+        let shared = Shared {
+            save: Box::leak(Box::new(Mutex::new(save))),
+            info: Box::leak(Box::new(Mutex::new(InfoData::default()))),
+            changed: Box::new(|_, _| {}),
+        };
+        let res = respond_rpc(&shared, r#"{"jsonrpc":"2.0","id":1,"method":"get_slot"}"#);
+        assert_eq!(res["id"], 1);
+        assert_eq!(res["result"]["save"]["tokens"], 5);
+        let res = respond_rpc(&shared, r#"{"jsonrpc":"2.0","id":2,"method":"progress"}"#);
+        assert_eq!(res["result"]["categories"][0]["name"], "Characters");
+        assert_eq!(respond_rpc(&shared, r#"{"id":3,"method":"fly"}"#)["error"]["code"], -32601);
+        assert_eq!(respond_rpc(&shared, "not json")["error"]["code"], -32700);
+    }
+
+    #[test]
+    fn streams_published_entries() {
+        let (tx, rx) = mpsc::channel();
+        SUBSCRIBERS.lock().push(tx);
+        publish(&[]);
+        SUBSCRIBERS.lock().clear();
+        let mut out = Vec::new();
+        stream_events(rx, &mut out).unwrap();
+        assert_eq!(out, b"event: change\ndata: []\n\n");
+    }
+}
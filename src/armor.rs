@@ -0,0 +1,76 @@
+//! Armor pieces sold in the shop. Save keeps bought pieces (`ar<id>=1`) and defence of the equipped one (`armor=`)
+
+use std::collections::HashSet;
+use serde::{Deserialize, Deserializer};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArmorPiece {
+    pub id: u32,
+    pub name: &'static str,
+    /// Value of `armor=` when the piece is equipped. Actual defence bonus is ten times bigger
+    pub defence: u32,
+}
+
+pub const PIECES: &[ArmorPiece] = &[
+    ArmorPiece { id: 1, name: "Endo Armor", defence: 1 },
+    ArmorPiece { id: 2, name: "Gold Armor", defence: 2 },
+    ArmorPiece { id: 3, name: "Blue Armor", defence: 10 },
+];
+
+pub fn piece(id: u32) -> Option<&'static ArmorPiece> {
+    PIECES.iter().find(|p| p.id == id)
+}
+
+/// Piece which gives exactly `defence` when equipped
+pub fn by_defence(defence: u32) -> Option<&'static ArmorPiece> {
+    PIECES.iter().find(|p| p.defence == defence)
+}
+
+/// Most expensive of `owned` pieces. Pieces are bought in order, so it tells how far the shop got
+pub fn best_owned(owned: &HashSet<u32>) -> Option<&'static ArmorPiece> {
+    PIECES.iter().rev().find(|p| owned.contains(&p.id))
+}
+
+/// Position of [`best_owned`] piece starting from 1, 0 if none is owned
+pub fn tier(owned: &HashSet<u32>) -> u32 {
+    best_owned(owned).and_then(|best| PIECES.iter().position(|p| p == best)).map_or(0, |i| i as u32 + 1)
+}
+
+/// Name of the piece or `#id` for unknown ones
+pub fn name(id: u32) -> String {
+    piece(id).map(|p| p.name.to_owned()).unwrap_or_else(|| format!("#{id}"))
+}
+
+/// Checks that equipped `defence` comes from one of `owned` pieces. Nothing equipped (0) is fine
+pub fn is_consistent(owned: &HashSet<u32>, defence: u32) -> bool {
+    defence == 0 || owned.iter().filter_map(|id| piece(*id)).any(|p| p.defence == defence)
+}
+
+/// Exports of version 1 stored the last bought piece as `armor_id`, meaning all pieces up to it
+pub fn deserialize_owned<'de, D: Deserializer<'de>>(d: D) -> Result<HashSet<u32>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Owned {
+        UpTo(u32),
+        Set(HashSet<u32>),
+    }
+    Ok(match Owned::deserialize(d)? {
+        Owned::UpTo(last) => (1..=last).collect(),
+        Owned::Set(set) => set,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn checks_consistency() {
+        let owned = HashSet::from([1, 3]);
+        assert!(is_consistent(&owned, 0));
+        assert!(is_consistent(&owned, 10));
+        assert!(!is_consistent(&owned, 2));
+        assert!(!is_consistent(&owned, 7));
+        assert_eq!(by_defence(2).map(|p| p.id), Some(2));
+    }
+}
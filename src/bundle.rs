@@ -0,0 +1,248 @@
+//! Zip archive with every save slot and info file of both games, for moving to another PC.
+//! Files are stored byte for byte under `saves/` and described by `manifest.toml`
+
+use std::cell::Cell;
+use std::error::Error;
+use std::io::{Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use clap::ValueEnum;
+use derive_more::Display;
+use serde::{Deserialize, Serialize};
+use zip::write::SimpleFileOptions;
+use crate::file_guard::{self, Seen};
+use crate::journal::Source;
+use crate::tokenizer::{construct_path, info_path};
+use crate::Game as FnafWorldGame;
+
+/// Version of the bundle layout. Increase when it changes incompatibly
+pub const BUNDLE_VERSION: u32 = 1;
+const MANIFEST: &str = "manifest.toml";
+const GAMES: [FnafWorldGame; 2] = [FnafWorldGame::WorldVanilla, FnafWorldGame::WorldRefreshed];
+
+#[derive(Debug, Display)]
+pub enum BundleError {
+    #[display("Archive has no {MANIFEST}, it isn't a save bundle")]
+    NoManifest,
+    #[display("Bundle version {_0} is newer than supported version {BUNDLE_VERSION}")]
+    UnsupportedVersion(u32),
+    #[display("File \"{_0}\" from the manifest is missing in the archive")]
+    Missing(String),
+    #[display("File \"{_0}\" is damaged")]
+    Damaged(String),
+    #[display("File \"{_0}\" refers to a slot which doesn't exist")]
+    UnknownSlot(String),
+}
+
+impl Error for BundleError {}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BundleFile {
+    pub name: String,
+    #[serde(with = "crate::export::game_name")]
+    pub game: FnafWorldGame,
+    /// Slot starting from 1, none for the info file
+    pub slot: Option<u8>,
+    pub crc32: u32,
+}
+
+impl BundleFile {
+    /// Where the file lives on this PC. Taken from game and slot, never from the name
+    pub fn target_path(&self) -> Result<PathBuf, BundleError> {
+        match self.slot {
+            Some(slot) => construct_path(self.game, slot.wrapping_sub(1)).map_err(|_| BundleError::UnknownSlot(self.name.clone())),
+            None => Ok(info_path(self.game)),
+        }
+    }
+
+    /// Readable name like "FNaF World Save 2"
+    pub fn title(&self) -> String {
+        let game = crate::export::game_title(self.game);
+        match self.slot {
+            Some(slot) => format!("{game} Save {slot}"),
+            None => format!("{game} Trophies"),
+        }
+    }
+}
+
+/// Bundled file with its content
+pub type Packed = (BundleFile, Vec<u8>);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    pub version: u32,
+    pub editor_version: String,
+    /// Unix time of the export
+    pub created: u64,
+    pub files: Vec<BundleFile>,
+}
+
+/// How a bundled file compares to the one on this PC
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+pub enum FileStatus {
+    #[display("New")]
+    New,
+    #[display("Same")]
+    Same,
+    #[display("Differs")]
+    Differs,
+}
+
+/// What to do with a bundled file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, ValueEnum)]
+pub enum Resolution {
+    #[display("Skip")]
+    Skip,
+    #[display("Overwrite")]
+    Overwrite,
+    /// Overwrite after copying the current file into backups
+    #[display("Back up and overwrite")]
+    Backup,
+}
+
+impl Resolution {
+    pub const ALL: [Self; 3] = [Self::Skip, Self::Overwrite, Self::Backup];
+}
+
+pub struct Incoming {
+    pub file: BundleFile,
+    pub status: FileStatus,
+    data: Vec<u8>,
+}
+
+impl Incoming {
+    pub fn default_resolution(&self) -> Resolution {
+        match self.status {
+            FileStatus::New => Resolution::Overwrite,
+            FileStatus::Same => Resolution::Skip,
+            FileStatus::Differs => Resolution::Backup,
+        }
+    }
+
+    /// Writes the file to its place. Returns whether anything was written
+    pub fn restore(&self, resolution: Resolution, source: Source) -> anyhow::Result<bool> {
+        if resolution == Resolution::Skip {
+            return Ok(false);
+        }
+        let path = self.file.target_path()?;
+        if resolution == Resolution::Backup && path.exists() {
+            crate::config::backup(&path)?;
+        }
+        let old = crate::changes::keys_of(self.file.game, self.file.slot);
+        // Restoring is an explicit choice to replace the file, whatever is there now
+        file_guard::write_bytes(path, self.data.clone(), &Cell::new(Seen::Unknown))?;
+        crate::changes::written_file(source, self.file.game, self.file.slot, &old);
+        Ok(true)
+    }
+}
+
+/// Existing save and info files of both games
+fn collect() -> anyhow::Result<Vec<Packed>> {
+    let mut files = Vec::new();
+    for game in GAMES {
+        let slots = (1..).map_while(|slot| Some((Some(slot), construct_path(game, slot - 1).ok()?)));
+        for (slot, path) in slots.chain([(None, info_path(game))]) {
+            if !path.exists() {
+                continue;
+            }
+            let data = std::fs::read(&path)?;
+            let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+            files.push((BundleFile { name, game, slot, crc32: crc32fast::hash(&data) }, data));
+        }
+    }
+    Ok(files)
+}
+
+pub fn write<W: Write + Seek>(w: W, files: Vec<Packed>) -> anyhow::Result<Manifest> {
+    let mut zip = zip::ZipWriter::new(w);
+    let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    let mut manifest = Manifest {
+        version: BUNDLE_VERSION,
+        editor_version: env!("CARGO_PKG_VERSION").to_owned(),
+        created: crate::timestamp::now(),
+        files: Vec::new(),
+    };
+    for (file, data) in files {
+        zip.start_file(format!("saves/{}", file.name), options)?;
+        zip.write_all(&data)?;
+        manifest.files.push(file);
+    }
+    zip.start_file(MANIFEST, options)?;
+    zip.write_all(toml::to_string_pretty(&manifest)?.as_bytes())?;
+    zip.finish()?;
+    Ok(manifest)
+}
+
+pub fn read<R: Read + Seek>(r: R) -> anyhow::Result<(Manifest, Vec<Packed>)> {
+    let mut zip = zip::ZipArchive::new(r)?;
+    let mut manifest = String::new();
+    zip.by_name(MANIFEST).map_err(|_| BundleError::NoManifest)?.read_to_string(&mut manifest)?;
+    let manifest: Manifest = toml::from_str(&manifest)?;
+    if manifest.version > BUNDLE_VERSION {
+        return Err(BundleError::UnsupportedVersion(manifest.version).into());
+    }
+
+    let mut files = Vec::new();
+    for file in &manifest.files {
+        file.target_path()?;
+        let mut data = Vec::new();
+        zip.by_name(&format!("saves/{}", file.name))
+            .map_err(|_| BundleError::Missing(file.name.clone()))?
+            .read_to_end(&mut data)?;
+        if crc32fast::hash(&data) != file.crc32 {
+            return Err(BundleError::Damaged(file.name.clone()).into());
+        }
+        files.push((file.clone(), data));
+    }
+    Ok((manifest, files))
+}
+
+/// Packs every existing save and info file into `path`
+pub fn export(path: &Path) -> anyhow::Result<Manifest> {
+    write(std::fs::File::create(path)?, collect()?)
+}
+
+/// Reads the bundle and compares its files with the ones on this PC
+pub fn open(path: &Path) -> anyhow::Result<(Manifest, Vec<Incoming>)> {
+    let (manifest, files) = read(std::fs::File::open(path)?)?;
+    let incoming = files.into_iter().map(|(file, data)| {
+        let status = match file.target_path().ok().and_then(|p| std::fs::read(p).ok()) {
+            None => FileStatus::New,
+            Some(current) if current == data => FileStatus::Same,
+            Some(_) => FileStatus::Differs,
+        };
+        Incoming { file, status, data }
+    }).collect();
+    Ok((manifest, incoming))
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+    use super::*;
+
+    fn file(name: &str, game: FnafWorldGame, slot: Option<u8>, data: &[u8]) -> Packed {
+        (BundleFile { name: name.to_owned(), game, slot, crc32: crc32fast::hash(data) }, data.to_vec())
+    }
+
+    #[test]
+    fn round_trip() {
+        let files = vec![
+            file("fnafw2", FnafWorldGame::WorldVanilla, Some(2), b"[fnafw]\ntokens=5"),
+            file("info1", FnafWorldGame::WorldRefreshed, None, b"[info]\nall=1"),
+        ];
+        let mut buf = Cursor::new(Vec::new());
+        write(&mut buf, files.clone()).unwrap();
+        let (manifest, read_files) = read(Cursor::new(buf.into_inner())).unwrap();
+        assert_eq!(manifest.version, BUNDLE_VERSION);
+        assert_eq!(read_files, files);
+        assert_eq!(read_files[0].0.title(), "FNaF World Save 2");
+        assert!(read_files[1].0.target_path().unwrap().ends_with("info1"));
+
+        let mut buf = Cursor::new(Vec::new());
+        let mut bad = file("fnafw9", FnafWorldGame::WorldVanilla, Some(9), b"");
+        bad.0.crc32 = 0;
+        write(&mut buf, vec![bad]).unwrap();
+        let err = read(Cursor::new(buf.into_inner())).unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(BundleError::UnknownSlot(_))));
+    }
+}
@@ -0,0 +1,183 @@
+//! Names, descriptions and effects of characters, chips and bytes. Data lives in `catalog.toml`.
+//! Mods can add or override entries with their own `catalog.toml` placed next to the executable
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::LazyLock;
+use clap::ValueEnum;
+use derive_more::Display;
+use serde::Deserialize;
+use crate::Game as FnafWorldGame;
+
+/// Ids above this are kept in the save but never shown in ui
+pub const MAX_UI_ID: u32 = 999;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, ValueEnum)]
+pub enum Kind {
+    #[display("character")]
+    Character,
+    #[display("chip")]
+    Chip,
+    #[display("byte")]
+    Byte,
+}
+
+impl Kind {
+    pub const ALL: [Self; 3] = [Self::Character, Self::Chip, Self::Byte];
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Entry {
+    pub id: u32,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub effects: Vec<String>,
+    #[serde(default)]
+    pub location: String,
+    /// Exists only in FNaF World: Refreshed
+    #[serde(default)]
+    pub refreshed_only: bool,
+}
+
+impl Entry {
+    /// Everything known about the entry in one line
+    pub fn info(&self) -> String {
+        let mut parts = vec![self.description.as_str()];
+        parts.extend(self.effects.iter().map(String::as_str));
+        if !self.location.is_empty() {
+            parts.push(&self.location);
+        }
+        parts.retain(|p| !p.is_empty());
+        parts.join(" · ")
+    }
+
+    fn matches(&self, query: &str) -> bool {
+        self.name.to_lowercase().contains(query)
+            || self.description.to_lowercase().contains(query)
+            || self.effects.iter().any(|e| e.to_lowercase().contains(query))
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct Catalog {
+    character: Vec<Entry>,
+    chip: Vec<Entry>,
+    byte: Vec<Entry>,
+}
+
+impl Catalog {
+    /// Adds entries of `other`, replacing ones with the same id
+    fn extend(&mut self, other: Catalog) {
+        for (entries, new) in [(&mut self.character, other.character), (&mut self.chip, other.chip), (&mut self.byte, other.byte)] {
+            entries.retain(|e| !new.iter().any(|n| n.id == e.id));
+            entries.extend(new);
+            entries.sort_by_key(|e| e.id);
+        }
+    }
+}
+
+fn mod_catalog_path() -> Option<PathBuf> {
+    Some(std::env::current_exe().ok()?.with_file_name("catalog.toml"))
+}
+
+fn read_mod_catalog(path: &std::path::Path) -> anyhow::Result<Catalog> {
+    Ok(toml::from_str(&std::fs::read_to_string(path)?)?)
+}
+
+static CATALOG: LazyLock<Catalog> = LazyLock::new(|| {
+    let mut catalog: Catalog = toml::from_str(include_str!("catalog.toml")).expect("Embedded catalog must be valid");
+    if let Some(path) = mod_catalog_path().filter(|p| p.exists()) {
+        match read_mod_catalog(&path) {
+            Ok(mods) => catalog.extend(mods),
+            Err(e) => eprintln!("Can't load {}: {e:#}", path.display()),
+        }
+    }
+    catalog
+});
+
+/// All known entries of `kind` sorted by id
+pub fn entries(kind: Kind) -> &'static [Entry] {
+    match kind {
+        Kind::Character => &CATALOG.character,
+        Kind::Chip => &CATALOG.chip,
+        Kind::Byte => &CATALOG.byte,
+    }
+}
+
+/// Biggest known id of `kind`
+pub fn max_id(kind: Kind) -> u32 {
+    entries(kind).iter().map(|e| e.id).max().unwrap_or(0)
+}
+
+/// Amount of ids of `kind` shown in ui for the game (ids from 1 up to the biggest one available there)
+pub fn count(kind: Kind, game: FnafWorldGame) -> u32 {
+    entries(kind).iter()
+        .filter(|e| !e.refreshed_only || game == FnafWorldGame::WorldRefreshed)
+        .map(|e| e.id)
+        .max()
+        .unwrap_or(0)
+}
+
+pub fn get(kind: Kind, id: u32) -> Option<&'static Entry> {
+    entries(kind).iter().find(|e| e.id == id)
+}
+
+/// Name of the entry, or `#id` if it is not in the catalog
+pub fn name(kind: Kind, id: u32) -> String {
+    get(kind, id).map(|e| e.name.clone()).unwrap_or_else(|| format!("#{id}"))
+}
+
+/// Entries of `kind` with name, description or effects containing `query` (case insensitive)
+pub fn search(kind: Kind, query: &str) -> impl Iterator<Item = &'static Entry> {
+    let query = query.trim().to_lowercase();
+    entries(kind).iter().filter(move |e| e.matches(&query))
+}
+
+/// For every id from 1 to [`max_id`] tells if it matches `query`. Used for filtering in ui
+pub fn matches(kind: Kind, query: &str) -> Vec<bool> {
+    let found = search(kind, query).map(|e| e.id).collect::<HashSet<_>>();
+    (1..=max_id(kind)).map(|id| found.contains(&id)).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn catalog_is_complete() {
+        for (kind, count) in [(Kind::Character, 48), (Kind::Chip, 26), (Kind::Byte, 24)] {
+            let ids = entries(kind).iter().map(|e| e.id).collect::<Vec<_>>();
+            assert_eq!(ids, (1..=count).collect::<Vec<_>>(), "{kind} ids");
+        }
+    }
+
+    #[test]
+    fn finds_by_name_and_effect() {
+        assert_eq!(name(Kind::Chip, 18), "Auto: Haste");
+        assert_eq!(name(Kind::Character, 999), "#999");
+        assert!(search(Kind::Chip, "HASTE").any(|e| e.id == 18));
+        assert!(search(Kind::Byte, "heals").all(|e| e.name.ends_with("Medic")));
+        assert!(matches(Kind::Character, "").iter().all(|m| *m));
+        assert_eq!(matches(Kind::Character, "nightmare").iter().filter(|m| **m).count(), 10);
+    }
+
+    #[test]
+    fn counts_per_game() {
+        assert_eq!(count(Kind::Chip, FnafWorldGame::WorldVanilla), 21);
+        assert_eq!(count(Kind::Chip, FnafWorldGame::WorldRefreshed), 26);
+        assert_eq!(count(Kind::Byte, FnafWorldGame::WorldRefreshed), 24);
+    }
+
+    #[test]
+    fn mods_override_and_add() {
+        let mut catalog: Catalog = toml::from_str(include_str!("catalog.toml")).unwrap();
+        let mods: Catalog = toml::from_str("[[chip]]\nid = 1\nname = \"Modded\"\n\n[[chip]]\nid = 40\nname = \"New\"").unwrap();
+        catalog.extend(mods);
+        assert_eq!(catalog.chip.iter().filter(|e| e.id == 1).map(|e| e.name.as_str()).collect::<Vec<_>>(), ["Modded"]);
+        assert_eq!(catalog.chip.last().unwrap().id, 40);
+        assert_eq!(catalog.character.len(), 48);
+    }
+}
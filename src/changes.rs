@@ -0,0 +1,53 @@
+//! Notifications about values written into saves and trophies. The journal, the control API and the stream overlay
+//! subscribe to them, so every write path reaches all of them
+
+use parking_lot::Mutex;
+use crate::discovery::{self, RawKeys};
+use crate::journal::{Entry, Source};
+use crate::tokenizer::{construct_path, info_path};
+use crate::Game as FnafWorldGame;
+
+type Subscriber = Box<dyn Fn(&[Entry]) + Send + Sync>;
+
+static SUBSCRIBERS: Mutex<Vec<Subscriber>> = Mutex::new(Vec::new());
+
+/// Calls `f` with the changed keys of every write, all of one file. `f` must not write saves itself
+pub fn subscribe(f: impl Fn(&[Entry]) + Send + Sync + 'static) {
+    SUBSCRIBERS.lock().push(Box::new(f));
+}
+
+/// Tells subscribers what changed between `old` and `new` contents of the slot (starting from 1, none for trophies)
+pub fn written(source: Source, game: FnafWorldGame, slot: Option<u8>, old: &RawKeys, new: &RawKeys) {
+    let changes = discovery::diff(old, new);
+    if changes.is_empty() {
+        return;
+    }
+    let time = crate::timestamp::now();
+    let entries = changes.iter().map(|change| {
+        let (key, old, new) = change.values();
+        Entry { time, source, game, slot, key: key.to_owned(), old, new }
+    }).collect::<Vec<_>>();
+    for f in SUBSCRIBERS.lock().iter() {
+        f(&entries);
+    }
+}
+
+/// Keys of the slot (starting from 1) or trophies as they are on disk, or held for the running game.
+/// None if they can't be read
+pub fn keys_of(game: FnafWorldGame, slot: Option<u8>) -> RawKeys {
+    let path = match slot {
+        Some(slot) => construct_path(game, slot.wrapping_sub(1)).ok(),
+        None => Some(info_path(game)),
+    };
+    let Some(path) = path else { return RawKeys::new() };
+    let keys = match crate::game_process::held(&path) {
+        Some(data) => discovery::parse_keys(&String::from_utf8_lossy(&data)),
+        None => discovery::read_keys(&path),
+    };
+    keys.unwrap_or_default()
+}
+
+/// Same as [`written`], for files copied without [`crate::tokenizer::SaveData`] or [`crate::tokenizer::InfoData`]
+pub fn written_file(source: Source, game: FnafWorldGame, slot: Option<u8>, old: &RawKeys) {
+    written(source, game, slot, old, &keys_of(game, slot));
+}
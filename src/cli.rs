@@ -0,0 +1,572 @@
+//! Command line interface. Used instead of the window when program is started with arguments
+
+use std::path::{Path, PathBuf};
+use clap::{Parser, Subcommand};
+use crate::bundle::{self, FileStatus, Resolution};
+use crate::catalog::{self, Kind};
+use crate::corpus;
+use crate::diff;
+use crate::discovery;
+use crate::export;
+use crate::journal::{self, Source};
+use crate::merge::{self, MergeRules, ScalarRule};
+use crate::quickstate;
+use crate::randomize::{self, Rules};
+use crate::report;
+use crate::save_file_watcher;
+use crate::script;
+use crate::share;
+use crate::Game as FnafWorldGame;
+use crate::tokenizer::{InfoData, SaveData};
+use crate::validate;
+
+#[derive(Parser)]
+#[command(version, about, long_about = None)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Shows what changed between two saves or two info files
+    Diff {
+        /// `<game>:<slot>`, `<game>:info` or path to a save file or JSON/TOML export
+        old: String,
+        /// Same as `old`
+        new: String,
+    },
+    /// Unites progress of two saves and writes it into a slot
+    Merge {
+        /// Save to take everything not related to progress from. Same format as in `diff`
+        first: String,
+        /// Save to add progress from
+        second: String,
+        /// Slot to write result into (`<game>:<slot>`)
+        #[arg(long)]
+        into: String,
+        #[arg(long, value_enum, default_value_t = ScalarRule::First)]
+        tokens: ScalarRule,
+        #[arg(long, value_enum, default_value_t = ScalarRule::First)]
+        pearls: ScalarRule,
+        #[arg(long, value_enum, default_value_t = ScalarRule::First)]
+        time: ScalarRule,
+        #[arg(long, value_enum, default_value_t = ScalarRule::First)]
+        save_pos: ScalarRule,
+        /// Only show what would change in the target slot
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Lists suspicious things in a save (equipped but not unlocked items, unknown ids, ...)
+    Check {
+        /// Same format as in `diff`
+        save: String,
+    },
+    /// Searches characters, chips and bytes by name, description or effect
+    Lookup {
+        /// Text to search for. Lists everything if not given
+        #[arg(default_value = "")]
+        query: String,
+        /// Search only among this kind
+        #[arg(long, value_enum)]
+        kind: Option<Kind>,
+    },
+    /// Writes a printable summary of a save and trophies of its game, as Markdown (.md) or HTML (.html)
+    Report {
+        /// Same format as in `diff`
+        save: String,
+        output: PathBuf,
+    },
+    /// Prints a short code of a save to send to another player
+    Share {
+        /// Same format as in `diff`
+        save: String,
+        /// Put trophies of the game into the code too
+        #[arg(long)]
+        trophies: bool,
+    },
+    /// Writes a save from a code made by `share` into a slot
+    Receive {
+        /// The code or `-` to read it from standard input
+        code: String,
+        /// Slot to write into (`<game>:<slot>`)
+        #[arg(long)]
+        into: String,
+        /// Also replace trophies if the code has them
+        #[arg(long)]
+        trophies: bool,
+        /// Only show what would change in the slot
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Packs every save and trophies file of both games into one zip archive
+    ExportAll {
+        archive: PathBuf,
+    },
+    /// Restores files from an archive made by `export-all`
+    ImportAll {
+        archive: PathBuf,
+        /// What to do with files which differ from the ones on this PC. Asks for each file if not given
+        #[arg(long, value_enum)]
+        on_conflict: Option<Resolution>,
+        /// Only show what would be restored
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Practice states of a slot: capture it and put it back after every attempt
+    #[command(subcommand)]
+    State(StateCommand),
+    /// Statistics over all save files in a folder: keys, their values, unknown keys, broken files and warnings
+    Analyze {
+        dir: PathBuf,
+        /// Print JSON instead of a table
+        #[arg(long)]
+        json: bool,
+    },
+    /// Runs a Rhai script on a slot. The script sees it as `save` and trophies of the game as `info`
+    Script {
+        /// Script file, `-` to read it from the input
+        script: PathBuf,
+        /// `<game>:<slot>`
+        #[arg(long)]
+        into: String,
+        /// Only show what the script would change
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Every change written into saves and trophies: list it, write entries again or undo them
+    #[command(subcommand)]
+    Journal(JournalCommand),
+    /// Logs raw keys of a slot which change while the game is played. Lines typed in are added as notes, Ctrl+Z/Ctrl+D stops
+    Discover {
+        /// `<game>:<slot>`
+        slot: String,
+    },
+    /// Gives a slot random party, chips and bytes. Same seed and options give the same team
+    Randomize {
+        /// Slot to randomize (`<game>:<slot>`)
+        into: String,
+        /// Number or any text. New one is made if not given
+        #[arg(long)]
+        seed: Option<String>,
+        #[arg(long, default_value_t = 8)]
+        party: usize,
+        #[arg(long, default_value_t = 1)]
+        min_level: u32,
+        #[arg(long, default_value_t = 20)]
+        max_level: u32,
+        #[arg(long, default_value_t = 4)]
+        chips: usize,
+        #[arg(long, default_value_t = 4)]
+        bytes: usize,
+        /// Chips to choose from, e.g. `1-10,15`. All by default
+        #[arg(long, default_value = "")]
+        allow_chips: String,
+        #[arg(long, default_value = "")]
+        allow_bytes: String,
+        /// Leave unlocked only the random team
+        #[arg(long)]
+        reset_progression: bool,
+        /// Only show what would change in the slot
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum StateCommand {
+    /// Copies the slot into a state
+    Capture {
+        /// `<game>:<slot>`
+        slot: String,
+        /// From 1 to 9
+        number: u8,
+    },
+    /// Puts a state back into the slot
+    Restore {
+        slot: String,
+        number: u8,
+    },
+    Delete {
+        slot: String,
+        number: u8,
+    },
+    /// Shows captured states of the slot
+    List {
+        slot: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum JournalCommand {
+    /// Shows the last entries with their numbers
+    List {
+        #[arg(long, default_value_t = 50)]
+        last: usize,
+    },
+    /// Writes values of entries from `from` to `to` again
+    Replay {
+        from: usize,
+        to: usize,
+    },
+    /// Puts back values which entries from `from` to `to` replaced, newest first
+    Revert {
+        from: usize,
+        to: usize,
+    },
+}
+
+/// Save or info file given on the command line
+pub enum Target {
+    Save(Box<SaveData>),
+    Info(InfoData),
+}
+
+/// Parses `<game>:<slot>` into game and slot starting from 1
+pub fn parse_slot(s: &str) -> anyhow::Result<(FnafWorldGame, u8)> {
+    let Some((game, slot)) = s.split_once(':') else {
+        anyhow::bail!("Expected `<game>:<slot>`, got \"{s}\"");
+    };
+    Ok((export::game_from_str(game)?, slot.parse()?))
+}
+
+/// Same as [`load_target`], but accepts only saves
+pub fn load_save(s: &str) -> anyhow::Result<SaveData> {
+    match load_target(s)? {
+        Target::Save(save) => Ok(*save),
+        Target::Info(_) => anyhow::bail!("\"{s}\" is an info file, not a save"),
+    }
+}
+
+/// Loads `<game>:<slot>`, `<game>:info` (e.g. `world:2`, `refreshed:info`) or a file
+pub fn load_target(s: &str) -> anyhow::Result<Target> {
+    if let Some((game, slot)) = s.split_once(':')
+        && let Ok(game) = export::game_from_str(game)
+    {
+        return match slot {
+            "info" => Ok(Target::Info(InfoData::read(game)?)),
+            slot => Ok(Target::Save(Box::new(SaveData::read(game, slot.parse()?)?))),
+        };
+    }
+    let path = Path::new(s);
+    if export::ExportFormat::from_path(path).is_err() && std::fs::read_to_string(path)?.starts_with("[info]") {
+        return Ok(Target::Info(InfoData::read_path(path)?));
+    }
+    Ok(Target::Save(Box::new(export::load_save_file(path)?)))
+}
+
+/// Parses arguments and runs requested command. Returns exit code
+pub fn run() -> i32 {
+    #[cfg(windows)]
+    unsafe {
+        // Release build has no console of its own, so print into the one we were started from
+        windows_sys::Win32::System::Console::AttachConsole(windows_sys::Win32::System::Console::ATTACH_PARENT_PROCESS);
+    }
+
+    let cli = Cli::parse();
+    let res = match cli.command {
+        Command::Diff { old, new } => run_diff(&old, &new),
+        Command::Merge { first, second, into, tokens, pearls, time, save_pos, dry_run } => {
+            run_merge(&first, &second, &into, MergeRules { tokens, pearls, time, save_pos }, dry_run)
+        },
+        Command::Check { save } => run_check(&save),
+        Command::Lookup { query, kind } => {
+            run_lookup(&query, kind);
+            Ok(())
+        },
+        Command::Report { save, output } => run_report(&save, &output),
+        Command::Share { save, trophies } => run_share(&save, trophies),
+        Command::Receive { code, into, trophies, dry_run } => run_receive(&code, &into, trophies, dry_run),
+        Command::ExportAll { archive } => run_export_all(&archive),
+        Command::ImportAll { archive, on_conflict, dry_run } => run_import_all(&archive, on_conflict, dry_run),
+        Command::State(cmd) => run_state(cmd),
+        Command::Journal(cmd) => run_journal(cmd),
+        Command::Discover { slot } => run_discover(&slot),
+        Command::Analyze { dir, json } => run_analyze(&dir, json),
+        Command::Script { script, into, dry_run } => run_script(&script, &into, dry_run),
+        Command::Randomize { into, seed, party, min_level, max_level, chips, bytes, allow_chips, allow_bytes, reset_progression, dry_run } => {
+            let rules = randomize::parse_ids(&allow_chips).and_then(|allowed_chips| Ok(Rules {
+                party,
+                levels: min_level..=max_level,
+                chips,
+                bytes,
+                allowed_chips,
+                allowed_bytes: randomize::parse_ids(&allow_bytes)?,
+                keep_progression: !reset_progression,
+            }));
+            rules.and_then(|rules| run_randomize(&into, seed, &rules, dry_run))
+        },
+    };
+    match res {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("Error: {e:#}");
+            1
+        }
+    }
+}
+
+fn run_diff(old: &str, new: &str) -> anyhow::Result<()> {
+    let changes = match (load_target(old)?, load_target(new)?) {
+        (Target::Save(old), Target::Save(new)) => diff::diff_saves(&old, &new),
+        (Target::Info(old), Target::Info(new)) => diff::diff_info(&old, &new),
+        _ => anyhow::bail!("Can't compare save with info file"),
+    };
+    print_changes(&changes);
+    Ok(())
+}
+
+fn print_changes(changes: &[diff::Change]) {
+    if changes.is_empty() {
+        println!("No differences");
+    }
+    for change in changes {
+        println!("{change}");
+    }
+}
+
+fn run_merge(first: &str, second: &str, into: &str, rules: MergeRules, dry_run: bool) -> anyhow::Result<()> {
+    let (game, slot) = parse_slot(into)?;
+    let target = SaveData::read(game, slot)?;
+    let mut merged = merge::merge(&load_save(first)?, &load_save(second)?, &rules);
+    merged.relocate(game, slot)?;
+    print_changes(&diff::diff_saves(&target, &merged));
+    if !dry_run {
+        merged.save(Source::Cli)?;
+        println!("Written into {into}");
+    }
+    Ok(())
+}
+
+fn run_report(save: &str, output: &Path) -> anyhow::Result<()> {
+    let save = load_save(save)?;
+    let info = InfoData::read(save.game())?;
+    report::write(&save, Some(&info), output)?;
+    println!("Report written into {}", output.display());
+    Ok(())
+}
+
+fn run_share(save: &str, trophies: bool) -> anyhow::Result<()> {
+    let save = load_save(save)?;
+    let info = if trophies { Some(InfoData::read(save.game())?) } else { None };
+    println!("{}", share::encode(&save, info.as_ref())?);
+    Ok(())
+}
+
+fn run_receive(code: &str, into: &str, trophies: bool, dry_run: bool) -> anyhow::Result<()> {
+    let code = match code {
+        "-" => std::io::read_to_string(std::io::stdin())?,
+        code => code.to_owned(),
+    };
+    let (game, slot) = parse_slot(into)?;
+    let target = SaveData::read(game, slot)?;
+    let (save, info) = share::decode(&code)?.into_parts(game, slot)?;
+    print_changes(&diff::diff_saves(&target, &save));
+    if !dry_run {
+        save.save(Source::Cli)?;
+        println!("Written into {into}");
+        if let Some(info) = info.filter(|_| trophies) {
+            info.save(Source::Cli)?;
+            println!("Trophies replaced");
+        }
+    }
+    Ok(())
+}
+
+fn run_export_all(archive: &Path) -> anyhow::Result<()> {
+    let manifest = bundle::export(archive)?;
+    for file in &manifest.files {
+        println!("Packed {}", file.title());
+    }
+    println!("{} files written into {}", manifest.files.len(), archive.display());
+    Ok(())
+}
+
+fn ask_resolution(title: &str) -> anyhow::Result<Resolution> {
+    loop {
+        print!("{title} differs from the one on this PC. [s]kip, [o]verwrite or [b]ack up and overwrite? ");
+        std::io::Write::flush(&mut std::io::stdout())?;
+        let mut answer = String::new();
+        if std::io::stdin().read_line(&mut answer)? == 0 {
+            return Ok(Resolution::Skip);
+        }
+        match answer.trim().to_ascii_lowercase().as_str() {
+            "s" | "skip" => return Ok(Resolution::Skip),
+            "o" | "overwrite" => return Ok(Resolution::Overwrite),
+            "b" | "backup" => return Ok(Resolution::Backup),
+            _ => {},
+        }
+    }
+}
+
+fn run_import_all(archive: &Path, on_conflict: Option<Resolution>, dry_run: bool) -> anyhow::Result<()> {
+    let (manifest, files) = bundle::open(archive)?;
+    println!("Bundle made by editor {} at {} UTC", manifest.editor_version, crate::timestamp::format(manifest.created));
+    for incoming in files {
+        let title = incoming.file.title();
+        let resolution = match (incoming.status, on_conflict) {
+            (FileStatus::Differs, Some(r)) => r,
+            (FileStatus::Differs, None) if !dry_run => ask_resolution(&title)?,
+            _ => incoming.default_resolution(),
+        };
+        if dry_run {
+            println!("{title}: {}, would {}", incoming.status, resolution.to_string().to_lowercase());
+        }
+        else if incoming.restore(resolution, Source::Cli)? {
+            println!("{title}: restored");
+        }
+        else {
+            println!("{title}: skipped ({})", incoming.status.to_string().to_lowercase());
+        }
+    }
+    Ok(())
+}
+
+fn run_state(cmd: StateCommand) -> anyhow::Result<()> {
+    match cmd {
+        StateCommand::Capture { slot, number } => {
+            let (game, s) = parse_slot(&slot)?;
+            quickstate::capture(game, s, number)?;
+            println!("{slot} captured into state {number}");
+        },
+        StateCommand::Restore { slot, number } => {
+            let (game, s) = parse_slot(&slot)?;
+            quickstate::restore(game, s, number, Source::Cli)?;
+            println!("State {number} restored into {slot}");
+        },
+        StateCommand::Delete { slot, number } => {
+            let (game, s) = parse_slot(&slot)?;
+            quickstate::delete(game, s, number)?;
+        },
+        StateCommand::List { slot } => {
+            let (game, s) = parse_slot(&slot)?;
+            for (number, time) in (1..).zip(quickstate::list(game, s)?) {
+                match time {
+                    Some(time) => println!("{number}: captured {} UTC", crate::timestamp::format(time)),
+                    None => println!("{number}: empty"),
+                }
+            }
+        },
+    }
+    Ok(())
+}
+
+fn run_journal(cmd: JournalCommand) -> anyhow::Result<()> {
+    let (range, revert) = match cmd {
+        JournalCommand::List { last } => {
+            let entries = journal::read()?;
+            for (number, entry) in (1..).zip(&entries).skip(entries.len().saturating_sub(last)) {
+                match entry {
+                    Some(entry) => println!("{number}: {entry}"),
+                    None => println!("{number}: unreadable"),
+                }
+            }
+            return Ok(());
+        },
+        JournalCommand::Replay { from, to } => (from..=to, false),
+        JournalCommand::Revert { from, to } => (from..=to, true),
+    };
+    let files = journal::apply(range, revert)?;
+    println!("{files} files written");
+    Ok(())
+}
+
+fn run_script(script: &Path, into: &str, dry_run: bool) -> anyhow::Result<()> {
+    let (game, slot) = parse_slot(into)?;
+    let script = if script == Path::new("-") { std::io::read_to_string(std::io::stdin())? } else { std::fs::read_to_string(script)? };
+    let run = script::run_on_slot(&script, game, slot)?;
+    for line in &run.output.printed {
+        println!("{line}");
+    }
+    let changes = run.changes().cloned().collect::<Vec<_>>();
+    print_changes(&changes);
+    if !dry_run && !changes.is_empty() {
+        run.write()?;
+        println!("Written into {into}");
+    }
+    Ok(())
+}
+
+fn run_analyze(dir: &Path, json: bool) -> anyhow::Result<()> {
+    let report = corpus::analyze(dir)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    }
+    else {
+        print!("{}", report.table());
+    }
+    Ok(())
+}
+
+fn run_discover(slot: &str) -> anyhow::Result<()> {
+    let (game, s) = parse_slot(slot)?;
+    let recording = std::sync::Arc::new(parking_lot::Mutex::new(discovery::Recording::start(game, s)?));
+    println!("Recording {slot} into {}", recording.lock().log_path().display());
+    let watched = recording.clone();
+    save_file_watcher::subscribe(move |w| {
+        if w.game != game || w.slot != Some(s) {
+            return;
+        }
+        // Only the game's changes are logged
+        if w.by_editor {
+            if let Err(e) = watched.lock().rebase() {
+                eprintln!("Error: {e:#}");
+            }
+            return;
+        }
+        match watched.lock().snapshot() {
+            Ok(changes) => changes.iter().for_each(|c| println!("  {c}")),
+            Err(e) => eprintln!("Error: {e:#}"),
+        }
+    })?;
+    for line in std::io::stdin().lines() {
+        let line = line?;
+        if !line.trim().is_empty() {
+            println!("{}", recording.lock().annotate(&line)?);
+        }
+    }
+    Ok(())
+}
+
+fn run_randomize(into: &str, seed: Option<String>, rules: &Rules, dry_run: bool) -> anyhow::Result<()> {
+    let (game, slot) = parse_slot(into)?;
+    let target = SaveData::read(game, slot)?;
+    let seed = seed.unwrap_or_else(randomize::new_seed);
+    println!("Seed: {seed}");
+    let res = randomize::randomize(&target, randomize::parse_seed(&seed), rules);
+    print_changes(&diff::diff_saves(&target, &res));
+    if !dry_run {
+        res.save(Source::Cli)?;
+        println!("Written into {into}");
+    }
+    Ok(())
+}
+
+fn run_check(save: &str) -> anyhow::Result<()> {
+    let warnings = validate::validate(&load_save(save)?);
+    if warnings.is_empty() {
+        println!("No problems found");
+    }
+    for warning in warnings {
+        println!("{warning}");
+    }
+    Ok(())
+}
+
+fn run_lookup(query: &str, kind: Option<Kind>) {
+    let kinds = kind.map(|k| vec![k]).unwrap_or_else(|| Kind::ALL.to_vec());
+    let mut found = false;
+    for kind in kinds {
+        for entry in catalog::search(kind, query) {
+            found = true;
+            println!("{kind} #{}: {}", entry.id, entry.name);
+            let info = entry.info();
+            if !info.is_empty() {
+                println!("    {info}");
+            }
+        }
+    }
+    if !found {
+        println!("Nothing found");
+    }
+}
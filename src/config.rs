@@ -0,0 +1,164 @@
+//! Editor's own settings and data, kept in `AppData/Roaming/fnaf_save_editor/config.toml`.
+//! In portable mode (a `portable` file next to the executable) everything is kept in `fnaf_save_editor` folder beside it
+
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use crate::api::ApiConfig;
+use crate::loadout::Loadout;
+use crate::overlay::OverlayConfig;
+use crate::pins::SlotPins;
+use crate::settings::{BackupPolicy, Session, Settings};
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub loadouts: Vec<Loadout>,
+    /// Hold writes while the game is running, see [`crate::game_process`]
+    pub defer_writes: bool,
+    /// Pinned values of slots, see [`crate::pins`]
+    pub pins: Vec<SlotPins>,
+    /// Control API for other programs, see [`crate::api`]
+    pub api: ApiConfig,
+    /// Progress file for stream overlays, see [`crate::overlay`]
+    pub overlay: OverlayConfig,
+    /// Preferences, see [`crate::settings`]
+    pub settings: Settings,
+    /// Restored on the next start
+    pub session: Session,
+}
+
+pub static CONFIG: LazyLock<Mutex<Config>> = LazyLock::new(|| Mutex::new(Config::load()));
+
+static CONFIG_DIR: LazyLock<PathBuf> = LazyLock::new(|| {
+    if let Some(dir) = portable_dir() {
+        return dir;
+    }
+    let mut path = home::home_dir().expect("Failed to find the home directory");
+    path.extend(["AppData", "Roaming", "fnaf_save_editor"]);
+    path
+});
+
+/// Folder next to the executable if there is a `portable` file beside it
+fn portable_dir() -> Option<PathBuf> {
+    let exe = std::env::current_exe().ok()?;
+    exe.with_file_name("portable").exists().then(|| exe.with_file_name("fnaf_save_editor"))
+}
+
+pub fn config_dir() -> PathBuf {
+    CONFIG_DIR.clone()
+}
+
+pub fn is_portable() -> bool {
+    portable_dir().is_some()
+}
+
+fn config_path() -> PathBuf {
+    config_dir().join("config.toml")
+}
+
+/// Copies the file into `backups` folder of the config dir with current time in its name. Returns the copy.
+/// It also counts as the backup of [`backup_before_write`]
+pub fn backup(path: &Path) -> anyhow::Result<PathBuf> {
+    let dir = config_dir().join("backups");
+    std::fs::create_dir_all(&dir)?;
+    let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    let stamp = crate::timestamp::file_stamp(crate::timestamp::now());
+    let mut names = std::fs::read_dir(&dir)?
+        .filter_map(|e| e.ok()?.file_name().into_string().ok())
+        .collect::<Vec<_>>();
+    // Copies of the same minute get the next number, so they are never older than a name freed by pruning
+    let copy = names.iter().filter_map(|n| backup_order(n, &name)).filter(|(s, _)| *s == stamp).map(|(_, c)| c).max();
+    let file = match copy {
+        Some(copy) => format!("{name}-{stamp}-{}", copy + 1),
+        None => format!("{name}-{stamp}"),
+    };
+    let backup = dir.join(&file);
+    std::fs::copy(path, &backup)?;
+    let mut backed_up = BACKED_UP.lock();
+    if !backed_up.iter().any(|p| p == path) {
+        backed_up.push(path.to_owned());
+    }
+    let keep = CONFIG.lock().settings.keep_backups;
+    if keep > 0 {
+        names.push(file);
+        for old in outdated(names, &name, keep) {
+            std::fs::remove_file(dir.join(old))?;
+        }
+    }
+    Ok(backup)
+}
+
+/// Time stamp and number of the copy (starting from 1) if `file` is a backup of the file `name`
+fn backup_order<'a>(file: &'a str, name: &str) -> Option<(&'a str, u32)> {
+    let rest = file.strip_prefix(name)?.strip_prefix('-')?;
+    let stamp_len = "YYYY-MM-DD-HH-MM".len();
+    let stamp = rest.get(..stamp_len).filter(|s| s.starts_with(|c: char| c.is_ascii_digit()))?;
+    match &rest[stamp_len..] {
+        "" => Some((stamp, 1)),
+        copy => Some((stamp, copy.strip_prefix('-')?.parse().ok()?)),
+    }
+}
+
+/// Backups of the file `name` beyond the newest `keep`
+fn outdated(mut names: Vec<String>, name: &str, keep: usize) -> Vec<String> {
+    names.retain(|n| backup_order(n, name).is_some());
+    names.sort_by(|a, b| backup_order(a, name).cmp(&backup_order(b, name)));
+    let extra = names.len().saturating_sub(keep);
+    names.truncate(extra);
+    names
+}
+
+/// Files already backed up since the editor started
+static BACKED_UP: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+/// Backs up the file before it's written for the first time since start, if the policy says so
+pub fn backup_before_write(path: &Path) -> anyhow::Result<()> {
+    let policy = CONFIG.lock().settings.backups;
+    if policy != BackupPolicy::FirstWrite || !path.exists() {
+        return Ok(());
+    }
+    if !BACKED_UP.lock().iter().any(|p| p == path) {
+        backup(path)?;
+    }
+    Ok(())
+}
+
+impl Config {
+    /// Reads config or gives defaults if there is none yet.
+    /// Broken config is moved to `config.toml.bak`, so saving the defaults doesn't destroy it
+    pub fn load() -> Self {
+        let path = config_path();
+        let Ok(data) = std::fs::read_to_string(&path) else { return Self::default() };
+        match toml::from_str(&data) {
+            Ok(config) => config,
+            Err(e) => {
+                eprintln!("Can't read {}: {e}", path.display());
+                let _ = std::fs::rename(&path, path.with_extension("toml.bak"));
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        std::fs::create_dir_all(config_dir())?;
+        std::fs::write(config_path(), toml::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn finds_outdated_backups() {
+        let names = [
+            "fnafw1-2024-05-02-10-00-10", "info1-2024-01-01-00-00", "fnafw1-2024-05-02-10-00-2", "fnafw1-2023-12-31-23-59", "fnafw1.lock",
+        ].map(str::to_owned).to_vec();
+        assert_eq!(outdated(names.clone(), "fnafw1", 2), ["fnafw1-2023-12-31-23-59"]);
+        assert_eq!(outdated(names.clone(), "fnafw1", 1), ["fnafw1-2023-12-31-23-59", "fnafw1-2024-05-02-10-00-2"]);
+        assert!(outdated(names, "info1", 1).is_empty());
+    }
+}
@@ -0,0 +1,249 @@
+//! Statistics over a folder of save files collected from players: which keys appear, their values,
+//! keys the editor doesn't understand, files which can't be read and validation warnings
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use serde::Serialize;
+use crate::save_parser::fnaf_world_parser;
+use crate::tokenizer::{SaveData, Token};
+use crate::validate;
+use crate::Game as FnafWorldGame;
+
+/// Files bigger than this aren't saves
+const MAX_SIZE: u64 = 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileKind {
+    Save,
+    Info,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct KeyStats {
+    /// Files the key appears in
+    pub files: usize,
+    pub min: u32,
+    pub max: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Failure {
+    pub path: PathBuf,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileWarnings {
+    pub path: PathBuf,
+    /// `world` or `world_refreshed`
+    pub game: &'static str,
+    /// Game wasn't known from the file name and was guessed from the contents
+    pub guessed: bool,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Report {
+    pub files: usize,
+    /// Readable files by kind
+    pub kinds: BTreeMap<FileKind, usize>,
+    /// Readable saves by game
+    pub games: BTreeMap<&'static str, usize>,
+    pub keys: BTreeMap<String, KeyStats>,
+    /// Keys parsed as [`Token::Other`] with amount of files they appear in
+    pub unknown_keys: BTreeMap<String, usize>,
+    pub failures: Vec<Failure>,
+    /// Only saves with warnings
+    pub warnings: Vec<FileWarnings>,
+}
+
+/// Game from the name the game gives its files, if it is one of them
+fn game_by_name(path: &Path) -> Option<FnafWorldGame> {
+    crate::save_file_watcher::slot_of(path).map(|(game, _)| game)
+}
+
+/// Why the data isn't a save: first line which isn't `key=value`
+fn parse_failure(data: &str) -> String {
+    let bad = data.lines().enumerate().skip(1).find(|(_, line)| {
+        let Some((key, value)) = line.split_once('=') else { return !line.trim().is_empty() };
+        !key.chars().all(|c| c.is_ascii_alphanumeric()) || value.trim().parse::<u32>().is_err()
+    });
+    match bad {
+        Some((i, line)) => format!("line {}: \"{}\" is not key=number", i + 1, line.chars().take(40).collect::<String>()),
+        None => "can't be parsed".to_owned(),
+    }
+}
+
+impl Report {
+    fn add_file(&mut self, path: &Path) {
+        self.files += 1;
+        let data = match std::fs::metadata(path) {
+            Ok(m) if m.len() > MAX_SIZE => Err("too big for a save".to_owned()),
+            Ok(_) => std::fs::read(path).map_err(|e| e.to_string())
+                .and_then(|d| String::from_utf8(d).map_err(|_| "not a text file".to_owned())),
+            Err(e) => Err(e.to_string()),
+        };
+        match data.and_then(|d| self.add_data(path, &d)) {
+            Ok(()) => {},
+            Err(reason) => self.failures.push(Failure { path: path.to_owned(), reason }),
+        }
+    }
+
+    fn add_data(&mut self, path: &Path, data: &str) -> Result<(), String> {
+        let kind = match data.lines().next().map(str::trim) {
+            Some("[fnafw]") => FileKind::Save,
+            Some("[info]") => FileKind::Info,
+            Some(header) => return Err(format!("unknown header \"{}\"", header.chars().take(40).collect::<String>())),
+            None => return Err("empty file".to_owned()),
+        };
+        let tokens = fnaf_world_parser(data).map_err(|_| parse_failure(data))?;
+        // Parser stops at the first bad line instead of failing
+        if tokens.len() < data.lines().skip(1).filter(|l| !l.trim().is_empty()).count() {
+            return Err(parse_failure(data));
+        }
+        *self.kinds.entry(kind).or_default() += 1;
+
+        let mut keys = BTreeMap::new();
+        for t in &tokens {
+            keys.insert(t.name.to_string(), t.value);
+        }
+        for (key, value) in keys {
+            let stats = self.keys.entry(key).or_insert(KeyStats { files: 0, min: value, max: value });
+            stats.files += 1;
+            stats.min = stats.min.min(value);
+            stats.max = stats.max.max(value);
+        }
+        let mut unknown = tokens.iter().cloned().map(Token::from).filter_map(|t| match t {
+            Token::Other(s) => s.split_once('=').map(|(k, _)| k.to_owned()),
+            _ => None,
+        }).collect::<Vec<_>>();
+        unknown.sort();
+        unknown.dedup();
+        for key in unknown {
+            *self.unknown_keys.entry(key).or_default() += 1;
+        }
+
+        if kind == FileKind::Save {
+            let mut save = SaveData::from(tokens.into_iter().map(Token::from));
+            let (game, guessed) = match game_by_name(path) {
+                Some(game) => (game, false),
+                // Vanilla save can't have anything which exists only in Refreshed
+                None => {
+                    let refreshed = validate::validate(&save).iter().any(|w| matches!(w, validate::Warning::RefreshedOnly(..)));
+                    (if refreshed { FnafWorldGame::WorldRefreshed } else { FnafWorldGame::WorldVanilla }, true)
+                },
+            };
+            save.relocate(game, 1).map_err(|e| e.to_string())?;
+            let game = crate::export::game_to_str(game);
+            *self.games.entry(game).or_default() += 1;
+            let warnings = validate::validate(&save);
+            if !warnings.is_empty() {
+                self.warnings.push(FileWarnings {
+                    path: path.to_owned(),
+                    game,
+                    guessed,
+                    warnings: warnings.iter().map(ToString::to_string).collect(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Readable text with aligned columns
+    pub fn table(&self) -> String {
+        let mut s = String::new();
+        let readable = self.files - self.failures.len();
+        let _ = writeln!(s, "Files: {}, readable: {readable}, failed: {}", self.files, self.failures.len());
+        for (kind, count) in &self.kinds {
+            let _ = writeln!(s, "  {kind:?}: {count}");
+        }
+        for (game, count) in &self.games {
+            let _ = writeln!(s, "  {game} saves: {count}");
+        }
+
+        let width = self.keys.keys().chain(self.unknown_keys.keys()).map(String::len).max().unwrap_or(3).max(3);
+        let _ = writeln!(s, "\n{:<width$}  {:>6}  {:>10}  {:>10}", "Key", "Files", "Min", "Max");
+        for (key, stats) in &self.keys {
+            let _ = writeln!(s, "{key:<width$}  {:>6}  {:>10}  {:>10}", stats.files, stats.min, stats.max);
+        }
+        if !self.unknown_keys.is_empty() {
+            let _ = writeln!(s, "\nUnknown keys\n{:<width$}  {:>6}", "Key", "Files");
+            for (key, files) in &self.unknown_keys {
+                let _ = writeln!(s, "{key:<width$}  {files:>6}");
+            }
+        }
+        if !self.failures.is_empty() {
+            let _ = writeln!(s, "\nFailures");
+            for f in &self.failures {
+                let _ = writeln!(s, "{}: {}", f.path.display(), f.reason);
+            }
+        }
+        if !self.warnings.is_empty() {
+            let _ = writeln!(s, "\nWarnings");
+            for f in &self.warnings {
+                let guessed = if f.guessed { ", guessed" } else { "" };
+                let _ = writeln!(s, "{} ({}{guessed})", f.path.display(), f.game);
+                for w in &f.warnings {
+                    let _ = writeln!(s, "  {w}");
+                }
+            }
+        }
+        s
+    }
+}
+
+fn walk(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            walk(&entry.path(), files)?;
+        }
+        else {
+            files.push(entry.path());
+        }
+    }
+    Ok(())
+}
+
+/// Analyzes every file in `dir` and its subfolders
+pub fn analyze(dir: &Path) -> anyhow::Result<Report> {
+    let mut files = Vec::new();
+    walk(dir, &mut files)?;
+    files.sort();
+    let mut report = Report::default();
+    for file in files {
+        report.add_file(&file);
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn collects_statistics() {
+        let dir = std::env::temp_dir().join(format!("fnaf_corpus_test_{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        std::fs::write(dir.join("fnafw1"), "[fnafw]\n1have=1\n1lv=4\ntokens=100\nmystery=3").unwrap();
+        std::fs::write(dir.join("sub").join("anon.sav"), "[fnafw]\n1have=1\n1lv=20\nc23=1").unwrap();
+        std::fs::write(dir.join("info1"), "[info]\nbeatgame1=1").unwrap();
+        std::fs::write(dir.join("broken"), "[fnafw]\n1lv=4\nthis is not a key").unwrap();
+        std::fs::write(dir.join("notes.txt"), "hello").unwrap();
+
+        let report = analyze(&dir).unwrap();
+        std::fs::remove_dir_all(dir).unwrap();
+        assert_eq!(report.files, 5);
+        assert_eq!(report.kinds, BTreeMap::from([(FileKind::Save, 2), (FileKind::Info, 1)]));
+        assert_eq!(report.games, BTreeMap::from([("world", 1), ("world_refreshed", 1)]));
+        assert_eq!(report.keys["1lv"], KeyStats { files: 2, min: 4, max: 20 });
+        assert_eq!(report.unknown_keys, BTreeMap::from([("mystery".to_owned(), 1)]));
+        assert_eq!(report.failures.len(), 2);
+        assert!(report.failures.iter().any(|f| f.reason == "line 3: \"this is not a key\" is not key=number"));
+        assert!(report.failures.iter().any(|f| f.reason.starts_with("unknown header")));
+        assert!(report.warnings.iter().all(|w| w.guessed == w.path.ends_with("anon.sav")));
+        assert!(report.table().contains("Unknown keys"));
+    }
+}
@@ -0,0 +1,219 @@
+//! Semantic comparison of two saves (or two info files)
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Display;
+use derive_more::Display;
+use crate::armor;
+use crate::catalog::{self, Kind};
+use crate::tokenizer::{Character, InfoData, SaveData, Token};
+
+#[derive(Debug, Clone, PartialEq, Eq, Display)]
+pub enum Change {
+    #[display("+ character {} (level {})", catalog::name(Kind::Character, *_0), _1.lvl + 1)]
+    CharacterAdded(u32, Character),
+    #[display("- character {} (level {})", catalog::name(Kind::Character, *_0), _1.lvl + 1)]
+    CharacterRemoved(u32, Character),
+    #[display("~ character {} level: {} -> {}", catalog::name(Kind::Character, *id), old + 1, new + 1)]
+    Level { id: u32, old: u32, new: u32 },
+    #[display("~ character {} exp to next: {old} -> {new}", catalog::name(Kind::Character, *id))]
+    Next { id: u32, old: u32, new: u32 },
+    #[display("+ chip {}", catalog::name(Kind::Chip, *_0))]
+    ChipAdded(u32),
+    #[display("- chip {}", catalog::name(Kind::Chip, *_0))]
+    ChipRemoved(u32),
+    #[display("+ byte {}", catalog::name(Kind::Byte, *_0))]
+    ByteAdded(u32),
+    #[display("- byte {}", catalog::name(Kind::Byte, *_0))]
+    ByteRemoved(u32),
+    #[display("{} jump {}", if *_1 { "+" } else { "-" }, _0 + 1)]
+    Jump(usize, bool),
+    #[display("{} guardian {}", if *_1 { "+" } else { "-" }, _0 + 1)]
+    Guardian(usize, bool),
+    #[display("{} clock {}", if *_1 { "+" } else { "-" }, _0 + 1)]
+    Clock(usize, bool),
+    #[display("+ ending {_0}")]
+    EndingAdded(u32),
+    #[display("- ending {_0}")]
+    EndingRemoved(u32),
+    #[display("~ {name}: {old} -> {new}")]
+    Field { name: &'static str, old: String, new: String },
+    #[display("~ raw {key}: {} -> {}", old.as_deref().unwrap_or("(none)"), new.as_deref().unwrap_or("(none)"))]
+    RawKey { key: String, old: Option<String>, new: Option<String> },
+}
+
+impl Change {
+    /// Splits change into columns (what, old value, new value) for side by side view
+    pub fn columns(&self) -> (String, String, String) {
+        let yes_no = |b: bool| if b { "yes".to_owned() } else { "no".to_owned() };
+        let character = |id: &u32| catalog::name(Kind::Character, *id);
+        let chip = |id: &u32| format!("Chip {}", catalog::name(Kind::Chip, *id));
+        let byte = |id: &u32| format!("Byte {}", catalog::name(Kind::Byte, *id));
+        match self {
+            Self::CharacterAdded(id, c) => (character(id), "-".into(), format!("level {}", c.lvl + 1)),
+            Self::CharacterRemoved(id, c) => (character(id), format!("level {}", c.lvl + 1), "-".into()),
+            Self::Level { id, old, new } => (format!("{} level", character(id)), (old + 1).to_string(), (new + 1).to_string()),
+            Self::Next { id, old, new } => (format!("{} exp to next", character(id)), old.to_string(), new.to_string()),
+            Self::ChipAdded(id) => (chip(id), yes_no(false), yes_no(true)),
+            Self::ChipRemoved(id) => (chip(id), yes_no(true), yes_no(false)),
+            Self::ByteAdded(id) => (byte(id), yes_no(false), yes_no(true)),
+            Self::ByteRemoved(id) => (byte(id), yes_no(true), yes_no(false)),
+            Self::Jump(idx, now) => (format!("Jump {}", idx + 1), yes_no(!now), yes_no(*now)),
+            Self::Guardian(idx, now) => (format!("Guardian {}", idx + 1), yes_no(!now), yes_no(*now)),
+            Self::Clock(idx, now) => (format!("Clock {}", idx + 1), yes_no(!now), yes_no(*now)),
+            Self::EndingAdded(id) => (format!("Ending {id}"), yes_no(false), yes_no(true)),
+            Self::EndingRemoved(id) => (format!("Ending {id}"), yes_no(true), yes_no(false)),
+            Self::Field { name, old, new } => (name.to_string(), old.clone(), new.clone()),
+            Self::RawKey { key, old, new } => (
+                format!("Raw \"{key}\""),
+                old.clone().unwrap_or_else(|| "-".into()),
+                new.clone().unwrap_or_else(|| "-".into()),
+            ),
+        }
+    }
+}
+
+fn field<T: PartialEq + Display>(changes: &mut Vec<Change>, name: &'static str, old: T, new: T) {
+    if old != new {
+        changes.push(Change::Field { name, old: old.to_string(), new: new.to_string() });
+    }
+}
+
+/// Party or equipment. Empty places (id 0) are shown as `-`
+fn list(changes: &mut Vec<Change>, name: &'static str, kind: Kind, old: &[u32], new: &[u32]) {
+    if old != new {
+        let join = |v: &[u32]| v.iter().map(|id| if *id == 0 { "-".to_owned() } else { catalog::name(kind, *id) }).collect::<Vec<_>>().join(", ");
+        changes.push(Change::Field { name, old: join(old), new: join(new) });
+    }
+}
+
+fn toggles(old: &[bool], new: &[bool], f: impl Fn(usize, bool) -> Change) -> impl Iterator<Item = Change> {
+    old.iter().zip(new.iter()).enumerate().filter(|(_, (o, n))| o != n).map(move |(i, (_, n))| f(i, *n))
+}
+
+/// Splits tokens into key and value by their text representation
+fn raw_keys<'a>(tokens: impl Iterator<Item = &'a Token>) -> BTreeMap<String, String> {
+    tokens
+        .map(|t| t.to_string())
+        .filter_map(|t| t.split_once('=').map(|(k, v)| (k.to_owned(), v.to_owned())))
+        .collect()
+}
+
+fn raw_key_changes(changes: &mut Vec<Change>, old: BTreeMap<String, String>, new: BTreeMap<String, String>) {
+    let keys = old.keys().chain(new.keys()).cloned().collect::<BTreeSet<_>>();
+    for key in keys {
+        let (o, n) = (old.get(&key), new.get(&key));
+        if o != n {
+            changes.push(Change::RawKey { key, old: o.cloned(), new: n.cloned() });
+        }
+    }
+}
+
+/// Lists everything that changed from `old` to `new` save
+pub fn diff_saves(old: &SaveData, new: &SaveData) -> Vec<Change> {
+    let mut changes = Vec::new();
+
+    let ids = old.characters.keys().chain(new.characters.keys()).copied().collect::<BTreeSet<_>>();
+    for id in ids {
+        match (old.characters.get(&id), new.characters.get(&id)) {
+            (None, Some(c)) => changes.push(Change::CharacterAdded(id, *c)),
+            (Some(c), None) => changes.push(Change::CharacterRemoved(id, *c)),
+            (Some(o), Some(n)) => {
+                if o.lvl != n.lvl {
+                    changes.push(Change::Level { id, old: o.lvl, new: n.lvl });
+                }
+                if o.next != n.next {
+                    changes.push(Change::Next { id, old: o.next, new: n.next });
+                }
+            },
+            (None, None) => {},
+        }
+    }
+    changes.extend(new.chips.difference(&old.chips).collect::<BTreeSet<_>>().into_iter().map(|c| Change::ChipAdded(*c)));
+    changes.extend(old.chips.difference(&new.chips).collect::<BTreeSet<_>>().into_iter().map(|c| Change::ChipRemoved(*c)));
+    changes.extend(new.bytes.difference(&old.bytes).collect::<BTreeSet<_>>().into_iter().map(|b| Change::ByteAdded(*b)));
+    changes.extend(old.bytes.difference(&new.bytes).collect::<BTreeSet<_>>().into_iter().map(|b| Change::ByteRemoved(*b)));
+    list(&mut changes, "Party", Kind::Character, &old.selected_characters, &new.selected_characters);
+    list(&mut changes, "Equipped chips", Kind::Chip, &old.selected_chips, &new.selected_chips);
+    list(&mut changes, "Equipped bytes", Kind::Byte, &old.selected_bytes, &new.selected_bytes);
+
+    changes.extend(toggles(&old.jumps, &new.jumps, Change::Jump));
+    changes.extend(toggles(&old.guardians, &new.guardians, Change::Guardian));
+    changes.extend(toggles(&old.clocks_found, &new.clocks_found, Change::Clock));
+
+    field(&mut changes, "Porkpatch button", old.porkpatch_button, new.porkpatch_button);
+    field(&mut changes, "Armor defence", old.armor, new.armor);
+    let pieces = |owned: &std::collections::HashSet<u32>| {
+        owned.iter().collect::<BTreeSet<_>>().into_iter().map(|id| armor::name(*id)).collect::<Vec<_>>().join(", ")
+    };
+    field(&mut changes, "Armor bought", pieces(&old.armor_owned), pieces(&new.armor_owned));
+    field(&mut changes, "Pearls", old.pearls, new.pearls);
+    field(&mut changes, "Tokens", old.tokens, new.tokens);
+    field(&mut changes, "Gamemode", old.mode as u32, new.mode as u32);
+    field(&mut changes, "Difficulty", old.diff.to_u32(), new.diff.to_u32());
+    let time = |t: (u32, u32, u32)| format!("{}:{:02}:{:02}", t.0, t.1, t.2);
+    field(&mut changes, "Time played", time(old.time), time(new.time));
+    let pos = |p: (u32, u32)| format!("x={} y={}", p.0, p.1);
+    field(&mut changes, "Save position", pos(old.save_pos), pos(new.save_pos));
+    field(&mut changes, "Dialog", old.dialog, new.dialog);
+    field(&mut changes, "Clock spawned", old.clock_spawned, new.clock_spawned);
+
+    raw_key_changes(&mut changes, raw_keys(old.flags.iter()), raw_keys(new.flags.iter()));
+    changes
+}
+
+/// Lists everything that changed from `old` to `new` info file
+pub fn diff_info(old: &InfoData, new: &InfoData) -> Vec<Change> {
+    let mut changes = Vec::new();
+    changes.extend(new.endings.difference(&old.endings).collect::<BTreeSet<_>>().into_iter().map(|e| Change::EndingAdded(*e)));
+    changes.extend(old.endings.difference(&new.endings).collect::<BTreeSet<_>>().into_iter().map(|e| Change::EndingRemoved(*e)));
+    field(&mut changes, "Pearl trophy", old.pearl, new.pearl);
+    field(&mut changes, "Fan trophy", old.fan, new.fan);
+    raw_key_changes(&mut changes, raw_keys(old.other.iter()), raw_keys(new.other.iter()));
+    changes
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tokenizer::test_save;
+
+    #[test]
+    fn detects_character_changes() {
+        let old = test_save("[fnafw]\n1have=1\n1lv=4\n1next=100\n2have=1");
+        let new = test_save("[fnafw]\n1have=1\n1lv=9\n1next=100\n3have=1");
+        let changes = diff_saves(&old, &new);
+        assert!(changes.contains(&Change::Level { id: 1, old: 4, new: 9 }));
+        assert!(changes.contains(&Change::CharacterRemoved(2, Character::default())));
+        assert!(changes.iter().any(|c| matches!(c, Change::CharacterAdded(3, _))));
+    }
+
+    #[test]
+    fn detects_chip_changes() {
+        let changes = diff_saves(&test_save("[fnafw]\nc1=1"), &test_save("[fnafw]\nc2=1"));
+        assert!(changes.contains(&Change::ChipAdded(2)));
+        assert!(changes.contains(&Change::ChipRemoved(1)));
+        assert!(changes.iter().any(|c| c.to_string() == "+ chip Headstart: Strength"));
+    }
+
+    #[test]
+    fn detects_world_flags() {
+        let changes = diff_saves(&test_save("[fnafw]\nsw1=1"), &test_save("[fnafw]\ng1=1"));
+        assert!(changes.contains(&Change::Jump(1, false)));
+        assert!(changes.contains(&Change::Clock(0, true)));
+    }
+
+    #[test]
+    fn detects_unknown_keys() {
+        let changes = diff_saves(&test_save("[fnafw]\nfoo=1"), &test_save("[fnafw]\nfoo=2\nbar=1"));
+        assert!(changes.contains(&Change::RawKey { key: "foo".into(), old: Some("1".into()), new: Some("2".into()) }));
+        assert!(changes.contains(&Change::RawKey { key: "bar".into(), old: None, new: Some("1".into()) }));
+    }
+
+    #[test]
+    fn skips_unchanged_values() {
+        let save = test_save("[fnafw]\n1have=1\nc1=1\ntokens=5\nfoo=1");
+        let changes = diff_saves(&save, &test_save("[fnafw]\n1have=1\nc1=1\ntokens=5\nfoo=2"));
+        assert!(!changes.iter().any(|c| matches!(c, Change::Field { name: "Tokens", .. })));
+        assert!(diff_saves(&save, &save).is_empty());
+    }
+}
@@ -0,0 +1,158 @@
+//! Key discovery: records how raw keys of a slot change while the game is played, to find out what unknown keys mean.
+//! Every recording is a text log in `discovery` folder of the config dir
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+use derive_more::Display;
+use crate::save_parser::fnaf_world_parser;
+use crate::tokenizer::construct_path;
+use crate::Game as FnafWorldGame;
+
+/// Raw keys of a save with their values
+pub type RawKeys = BTreeMap<String, u32>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Display)]
+pub enum KeyChange {
+    #[display("+ {_0}={_1}")]
+    Added(String, u32),
+    #[display("- {_0}={_1}")]
+    Removed(String, u32),
+    #[display("~ {_0}: {_1} -> {_2}")]
+    Changed(String, u32, u32),
+}
+
+/// Keys of the file, none if there is no file yet
+pub fn read_keys(path: &Path) -> anyhow::Result<RawKeys> {
+    let data = match std::fs::read_to_string(path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(RawKeys::new()),
+        Err(e) => return Err(e.into()),
+    };
+    parse_keys(&data)
+}
+
+/// Keys of save or info file contents
+pub fn parse_keys(data: &str) -> anyhow::Result<RawKeys> {
+    Ok(fnaf_world_parser(data)?.into_iter().map(|t| (t.name.to_string(), t.value)).collect())
+}
+
+/// Changes from `old` to `new`, ordered by key
+pub fn diff(old: &RawKeys, new: &RawKeys) -> Vec<KeyChange> {
+    let mut changes = Vec::new();
+    for (key, value) in old {
+        match new.get(key) {
+            None => changes.push(KeyChange::Removed(key.clone(), *value)),
+            Some(v) if v != value => changes.push(KeyChange::Changed(key.clone(), *value, *v)),
+            Some(_) => {},
+        }
+    }
+    changes.extend(new.iter().filter(|(k, _)| !old.contains_key(*k)).map(|(k, v)| KeyChange::Added(k.clone(), *v)));
+    changes.sort_by(|a, b| key_of(a).cmp(key_of(b)));
+    changes
+}
+
+fn key_of(change: &KeyChange) -> &str {
+    change.values().0
+}
+
+impl KeyChange {
+    /// Key with its value before and after, none if it wasn't there
+    pub fn values(&self) -> (&str, Option<u32>, Option<u32>) {
+        match self {
+            KeyChange::Added(k, v) => (k, None, Some(*v)),
+            KeyChange::Removed(k, v) => (k, Some(*v), None),
+            KeyChange::Changed(k, old, new) => (k, Some(*old), Some(*new)),
+        }
+    }
+}
+
+pub struct Recording {
+    game: FnafWorldGame,
+    slot: u8,
+    save: PathBuf,
+    log: PathBuf,
+    last: RawKeys,
+}
+
+impl Recording {
+    /// Takes the first snapshot of the slot (starting from 1) and creates the log
+    pub fn start(game: FnafWorldGame, slot: u8) -> anyhow::Result<Self> {
+        let save = construct_path(game, slot.wrapping_sub(1))?;
+        let name = save.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        let dir = crate::config::config_dir().join("discovery");
+        std::fs::create_dir_all(&dir)?;
+        let now = crate::timestamp::now();
+        let res = Self {
+            game,
+            slot,
+            log: dir.join(format!("{name}-{}.log", crate::timestamp::file_stamp(now))),
+            last: read_keys(&save)?,
+            save,
+        };
+        res.append(&format!("{} recording {name}, {} keys\n", crate::timestamp::format_seconds(now), res.last.len()))?;
+        Ok(res)
+    }
+
+    pub fn is_of(&self, game: FnafWorldGame, slot: u8) -> bool {
+        self.game == game && self.slot == slot
+    }
+
+    pub fn log_path(&self) -> &Path {
+        &self.log
+    }
+
+    fn append(&self, text: &str) -> anyhow::Result<()> {
+        let mut file = std::fs::OpenOptions::new().create(true).append(true).open(&self.log)?;
+        file.write_all(text.as_bytes())?;
+        Ok(())
+    }
+
+    /// Compares the file with the previous snapshot and logs what changed
+    pub fn snapshot(&mut self) -> anyhow::Result<Vec<KeyChange>> {
+        let keys = read_keys(&self.save)?;
+        let changes = diff(&self.last, &keys);
+        if !changes.is_empty() {
+            let mut text = format!("{} written\n", crate::timestamp::format_seconds(crate::timestamp::now()));
+            for change in &changes {
+                let _ = writeln!(text, "  {change}");
+            }
+            self.append(&text)?;
+        }
+        self.last = keys;
+        Ok(changes)
+    }
+
+    /// Takes a new snapshot without logging it, after the editor wrote the file
+    pub fn rebase(&mut self) -> anyhow::Result<()> {
+        self.last = read_keys(&self.save)?;
+        Ok(())
+    }
+
+    /// Adds a note of what just happened in the game
+    pub fn annotate(&self, note: &str) -> anyhow::Result<String> {
+        let line = format!("{} note: {}", crate::timestamp::format_seconds(crate::timestamp::now()), note.trim());
+        self.append(&format!("{line}\n"))?;
+        Ok(line)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn finds_key_changes() {
+        let keys = |s: &str| parse_keys(s).unwrap();
+        let old = keys("[fnafw]\n1lv=4\nw3=1\nfoo=7\n");
+        let new = keys("[fnafw]\n1lv=5\nfoo=7\nbar=2\n");
+        assert_eq!(diff(&old, &new), [
+            KeyChange::Changed("1lv".to_owned(), 4, 5),
+            KeyChange::Added("bar".to_owned(), 2),
+            KeyChange::Removed("w3".to_owned(), 1),
+        ]);
+        assert!(diff(&new, &new).is_empty());
+        assert_eq!(KeyChange::Changed("1lv".to_owned(), 4, 5).to_string(), "~ 1lv: 4 -> 5");
+    }
+}
@@ -0,0 +1,230 @@
+//! Human readable (JSON/TOML) representation of the whole save state.
+//! Keys are written in a stable order so exports can be kept in git and reviewed in diffs.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::error::Error;
+use std::path::Path;
+use derive_more::Display;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use crate::tokenizer::{InfoData, SaveData};
+use crate::Game as FnafWorldGame;
+
+/// Version of the export format. Increase when the layout changes incompatibly
+pub const EXPORT_VERSION: u32 = 2;
+
+#[derive(Debug, Display)]
+pub enum ExportError {
+    #[display("Unknown export format of \"{_0}\". Use .json or .toml")]
+    UnknownFormat(String),
+    #[display("Export version {_0} is newer than supported version {EXPORT_VERSION}")]
+    UnsupportedVersion(u32),
+    #[display("Unknown game \"{_0}\"")]
+    UnknownGame(String),
+}
+
+impl Error for ExportError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Json,
+    Toml,
+}
+
+impl ExportFormat {
+    /// Guesses format from file extension
+    pub fn from_path(path: &Path) -> Result<Self, ExportError> {
+        match path.extension().and_then(|e| e.to_str()).map(|e| e.to_ascii_lowercase()).as_deref() {
+            Some("json") => Ok(Self::Json),
+            Some("toml") => Ok(Self::Toml),
+            _ => Err(ExportError::UnknownFormat(path.display().to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SaveExport {
+    pub version: u32,
+    #[serde(with = "game_name")]
+    pub game: FnafWorldGame,
+    pub slot: u8,
+    pub save: SaveData,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub info: Option<InfoData>,
+}
+
+impl SaveExport {
+    pub fn new(save: &SaveData, info: Option<&InfoData>) -> Self {
+        Self {
+            version: EXPORT_VERSION,
+            game: save.game(),
+            slot: save.slot(),
+            save: save.clone(),
+            info: info.cloned(),
+        }
+    }
+
+    pub fn to_string(&self, format: ExportFormat) -> anyhow::Result<String> {
+        let data = match format {
+            ExportFormat::Json => serde_json::to_string_pretty(self)?,
+            ExportFormat::Toml => toml::to_string_pretty(self)?,
+        };
+        Ok(data)
+    }
+
+    pub fn from_str(data: &str, format: ExportFormat) -> anyhow::Result<Self> {
+        let res: Self = match format {
+            ExportFormat::Json => serde_json::from_str(data)?,
+            ExportFormat::Toml => toml::from_str(data)?,
+        };
+        if res.version > EXPORT_VERSION {
+            return Err(ExportError::UnsupportedVersion(res.version).into());
+        }
+        Ok(res)
+    }
+
+    pub fn write(&self, path: &Path) -> anyhow::Result<()> {
+        let data = self.to_string(ExportFormat::from_path(path)?)?;
+        std::fs::write(path, data)?;
+        Ok(())
+    }
+
+    pub fn read(path: &Path) -> anyhow::Result<Self> {
+        let format = ExportFormat::from_path(path)?;
+        let data = std::fs::read_to_string(path)?;
+        Self::from_str(&data, format)
+    }
+
+    /// Returns save and info placed into specified game and slot (starting from 1)
+    pub fn into_parts(self, game: FnafWorldGame, slot: u8) -> anyhow::Result<(SaveData, Option<InfoData>)> {
+        let mut save = self.save;
+        save.relocate(game, slot)?;
+        let info = self.info.map(|mut info| {
+            info.relocate(game);
+            info
+        });
+        Ok((save, info))
+    }
+}
+
+/// Reads save from either raw save file or JSON/TOML export
+pub fn load_save_file(path: &Path) -> anyhow::Result<SaveData> {
+    match ExportFormat::from_path(path) {
+        Ok(_) => Ok(SaveExport::read(path)?.save),
+        Err(_) => SaveData::read_path(path),
+    }
+}
+
+pub fn game_to_str(game: FnafWorldGame) -> &'static str {
+    match game {
+        FnafWorldGame::WorldVanilla => "world",
+        FnafWorldGame::WorldRefreshed => "world_refreshed",
+    }
+}
+
+/// Name of the game as it calls itself
+pub fn game_title(game: FnafWorldGame) -> &'static str {
+    match game {
+        FnafWorldGame::WorldVanilla => "FNaF World",
+        FnafWorldGame::WorldRefreshed => "FNaF World: Refreshed",
+    }
+}
+
+pub fn game_from_str(s: &str) -> Result<FnafWorldGame, ExportError> {
+    match s.to_ascii_lowercase().as_str() {
+        "world" | "fnafw" | "vanilla" => Ok(FnafWorldGame::WorldVanilla),
+        "world_refreshed" | "fnafwr" | "refreshed" => Ok(FnafWorldGame::WorldRefreshed),
+        _ => Err(ExportError::UnknownGame(s.to_owned())),
+    }
+}
+
+/// Game written by its name (`world`, `world_refreshed`)
+pub mod game_name {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(game: &FnafWorldGame, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(game_to_str(*game))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<FnafWorldGame, D::Error> {
+        let s = String::deserialize(d)?;
+        game_from_str(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+/// `HashMap` with keys sorted and written as strings (TOML allows only string keys)
+pub mod ordered_map {
+    use std::fmt::Display;
+    use std::hash::Hash;
+    use std::str::FromStr;
+    use super::*;
+
+    pub fn serialize<S, K, V>(map: &HashMap<K, V>, s: S) -> Result<S::Ok, S::Error>
+    where S: Serializer, K: Ord + Display, V: Serialize
+    {
+        s.collect_map(map.iter().collect::<BTreeMap<_, _>>().into_iter().map(|(k, v)| (k.to_string(), v)))
+    }
+
+    pub fn deserialize<'de, D, K, V>(d: D) -> Result<HashMap<K, V>, D::Error>
+    where D: Deserializer<'de>, K: Eq + Hash + FromStr, K::Err: Display, V: Deserialize<'de>
+    {
+        HashMap::<String, V>::deserialize(d)?
+            .into_iter()
+            .map(|(k, v)| k.parse().map(|k| (k, v)).map_err(serde::de::Error::custom))
+            .collect()
+    }
+}
+
+/// Serializes `HashSet` as sorted list
+pub fn ordered_set<S, T>(set: &HashSet<T>, s: S) -> Result<S::Ok, S::Error>
+where S: Serializer, T: Ord + Serialize
+{
+    set.iter().collect::<BTreeSet<_>>().serialize(s)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tokenizer::test_save;
+    use crate::tokenizer::Token;
+
+    fn sample() -> SaveData {
+        let data = "[fnafw]\n1have=1\n1lv=5\n1next=296\n12have=1\n12lv=2\n12next=150\nc3=1\np7=1\ns1=1\nactive1=3\nactive1b=7\ntokens=120\ndiff=2\nx=1500\ny=300\nkey=1\nunknown=42";
+        test_save(data)
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut info = InfoData::default();
+        info.endings.insert(2);
+        info.other.push(Token::Other("strange=7".to_owned()));
+        let export = SaveExport::new(&sample(), Some(&info));
+
+        for format in [ExportFormat::Json, ExportFormat::Toml] {
+            let text = export.to_string(format).unwrap();
+            let back = SaveExport::from_str(&text, format).unwrap();
+            assert_eq!(back.to_string(format).unwrap(), text);
+            assert_eq!(back.save.characters[&12].lvl, 2);
+            assert!(back.save.flags.contains(&Token::Other("unknown=42".to_owned())));
+            assert_eq!(back.info.unwrap().other, info.other);
+        }
+    }
+
+    #[test]
+    fn reads_version_1_armor() {
+        let mut value = serde_json::to_value(SaveExport::new(&sample(), None)).unwrap();
+        value["version"] = 1.into();
+        let save = value["save"].as_object_mut().unwrap();
+        save.remove("armor_owned");
+        save.insert("armor_id".to_owned(), 2.into());
+        let export = SaveExport::from_str(&value.to_string(), ExportFormat::Json).unwrap();
+        assert_eq!(export.save.armor_owned, HashSet::from([1, 2]));
+    }
+
+    #[test]
+    fn rejects_newer_version() {
+        let mut export = SaveExport::new(&sample(), None);
+        export.version = EXPORT_VERSION + 1;
+        let text = export.to_string(ExportFormat::Json).unwrap();
+        assert!(SaveExport::from_str(&text, ExportFormat::Json).is_err());
+    }
+}
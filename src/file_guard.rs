@@ -0,0 +1,188 @@
+//! Protection of save files from other editors. Writes are serialized between processes with a lock file
+//! in `locks` folder of the config dir, and a file which changed on disk since it was read isn't overwritten.
+//! The lock file keeps the checksum of the last write, so the editor's own writes can be told from the game's
+
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+use derive_more::Display;
+use parking_lot::Mutex;
+
+type ConflictHandler = Box<dyn Fn(PathBuf) + Send>;
+
+/// Called with the file when a conflict is found
+static ON_CONFLICT: Mutex<Option<ConflictHandler>> = Mutex::new(None);
+/// Held contents which weren't written because the game changed the file, by path
+static REFUSED: Mutex<BTreeMap<PathBuf, Vec<u8>>> = Mutex::new(BTreeMap::new());
+
+/// What was on disk when a save was read or last written
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Seen {
+    /// Not read from its file, nothing to compare with
+    #[default]
+    Unknown,
+    Missing,
+    Checksum(u32),
+}
+
+impl Seen {
+    pub fn of(data: Option<&[u8]>) -> Self {
+        data.map_or(Self::Missing, |d| Self::Checksum(crc32fast::hash(d)))
+    }
+}
+
+#[derive(Debug, Display)]
+#[display("{} was changed by another program since it was loaded", _0.display())]
+pub struct ConflictError(pub PathBuf);
+
+impl Error for ConflictError {}
+
+fn on_disk(path: &Path) -> std::io::Result<Seen> {
+    match std::fs::read(path) {
+        Ok(data) => Ok(Seen::of(Some(&data))),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Seen::Missing),
+        Err(e) => Err(e),
+    }
+}
+
+/// Fails if the file differs from what was `seen`
+pub fn check(path: &Path, seen: Seen) -> anyhow::Result<()> {
+    if seen != Seen::Unknown && on_disk(path)? != seen {
+        return Err(ConflictError(path.to_owned()).into());
+    }
+    Ok(())
+}
+
+/// Sets what is called when [`write`] or [`write_held`] refuses to overwrite a file
+pub fn on_conflict(f: impl Fn(PathBuf) + Send + 'static) {
+    *ON_CONFLICT.lock() = Some(Box::new(f));
+}
+
+fn conflict(path: PathBuf) {
+    if let Some(f) = ON_CONFLICT.lock().as_ref() {
+        f(path);
+    }
+}
+
+/// Held contents of the file refused by [`write_held`], to write them anyway
+pub fn take_refused(path: &Path) -> Option<Vec<u8>> {
+    REFUSED.lock().remove(path)
+}
+
+fn lock_path(path: &Path) -> PathBuf {
+    let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    crate::config::config_dir().join("locks").join(format!("{name}.lock"))
+}
+
+/// Exclusive lock shared by all editor instances, released when dropped
+fn lock(path: &Path) -> anyhow::Result<std::fs::File> {
+    let lock_path = lock_path(path);
+    if let Some(dir) = lock_path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    // Not truncated before it's locked, the checksum in it may be written right now
+    let file = std::fs::OpenOptions::new().create(true).truncate(false).write(true).open(lock_path)?;
+    file.lock()?;
+    Ok(file)
+}
+
+/// Writes the file and keeps its checksum in the held `lock`
+fn store(lock: &mut std::fs::File, path: &Path, data: &[u8]) -> anyhow::Result<()> {
+    std::fs::write(path, data)?;
+    lock.set_len(0)?;
+    lock.write_all(crc32fast::hash(data).to_string().as_bytes())?;
+    Ok(())
+}
+
+/// Whether the file is as an editor (this or another instance) last wrote it
+pub fn written_by_editor(path: &Path) -> bool {
+    let Ok(last) = std::fs::read_to_string(lock_path(path)) else { return false };
+    matches!(on_disk(path), Ok(Seen::Checksum(sum)) if last.trim() == sum.to_string())
+}
+
+/// Writes the file unless it changed since it was `seen`, which is updated afterwards.
+/// While writes are held for the running game, which rewrites saves by itself, the check waits until it exits
+pub fn write(path: PathBuf, data: String, seen: &Cell<Seen>) -> anyhow::Result<()> {
+    write_bytes(path, data.into_bytes(), seen)
+}
+
+/// Same as [`write`], for whole files copied as they are
+pub fn write_bytes(path: PathBuf, data: Vec<u8>, seen: &Cell<Seen>) -> anyhow::Result<()> {
+    let mut lock = lock(&path)?;
+    let holds = crate::game_process::holds_writes();
+    if !holds && let Err(e) = check(&path, seen.get()) {
+        conflict(path);
+        return Err(e);
+    }
+    crate::config::backup_before_write(&path)?;
+    let base = seen.replace(Seen::of(Some(&data)));
+    if holds {
+        crate::game_process::hold(path, data, base);
+        return Ok(());
+    }
+    crate::game_process::release(&path);
+    store(&mut lock, &path, &data)
+}
+
+/// Writes a file held while the game ran, unless the game changed it since the first held write.
+/// Then the contents are kept for [`take_refused`] and the conflict is reported. Returns whether the file was written
+pub fn write_held(path: &Path, held: &crate::game_process::Held) -> anyhow::Result<bool> {
+    let mut lock = lock(path)?;
+    match check(path, held.base) {
+        Ok(()) => {},
+        Err(e) if e.is::<ConflictError>() => {
+            REFUSED.lock().insert(path.to_owned(), held.data.clone());
+            conflict(path.to_owned());
+            return Ok(false);
+        },
+        Err(e) => return Err(e),
+    }
+    store(&mut lock, path, &held.data)?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn detects_changes_on_disk() {
+        let file = std::env::temp_dir().join(format!("fnaf_guard_test_{}", std::process::id()));
+        assert!(check(&file, Seen::Unknown).is_ok());
+        assert!(check(&file, Seen::Missing).is_ok());
+
+        std::fs::write(&file, "[fnafw]\ntokens=5").unwrap();
+        let err = check(&file, Seen::Missing).unwrap_err();
+        assert!(err.downcast_ref::<ConflictError>().is_some_and(|e| e.0 == file));
+
+        let seen = Seen::of(Some(b"[fnafw]\ntokens=5"));
+        assert!(check(&file, seen).is_ok());
+        std::fs::write(&file, "[fnafw]\ntokens=6").unwrap();
+        assert!(check(&file, seen).is_err());
+        assert!(check(&file, Seen::Unknown).is_ok());
+        std::fs::remove_file(file).unwrap();
+    }
+
+    #[test]
+    fn refuses_held_writes_over_newer_files() {
+        let file = std::env::temp_dir().join(format!("fnaf_guard_held_test_{}", std::process::id()));
+        std::fs::write(&file, "[fnafw]\ntokens=5").unwrap();
+        let base = Seen::of(Some(b"[fnafw]\ntokens=5"));
+        let held = |data: &str| crate::game_process::Held { data: data.as_bytes().to_vec(), base };
+
+        assert!(write_held(&file, &held("[fnafw]\ntokens=9")).unwrap());
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "[fnafw]\ntokens=9");
+        assert!(take_refused(&file).is_none());
+        assert!(written_by_editor(&file));
+
+        // The game saved since the edits were held
+        assert!(!write_held(&file, &held("[fnafw]\ntokens=7")).unwrap());
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "[fnafw]\ntokens=9");
+        assert_eq!(take_refused(&file).unwrap(), b"[fnafw]\ntokens=7");
+        std::fs::write(&file, "[fnafw]\ntokens=8").unwrap();
+        assert!(!written_by_editor(&file));
+        std::fs::remove_file(file).unwrap();
+    }
+}
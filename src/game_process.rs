@@ -0,0 +1,165 @@
+//! Detection of a running game. While it runs it keeps the save in memory and overwrites edits when it saves,
+//! so writes can be held until it exits
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use parking_lot::Mutex;
+use crate::file_guard::Seen;
+
+/// Executable names of both games, lowercase
+pub const PROCESS_NAMES: &[&str] = &[
+    "fnaf_world.exe",
+    "fnaf world.exe",
+    "fnafworld.exe",
+    "fnaf_world_refreshed.exe",
+    "fnaf world refreshed.exe",
+];
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+static RUNNING: AtomicBool = AtomicBool::new(false);
+/// Files held until the game exits, by path
+static PENDING: Mutex<BTreeMap<PathBuf, Held>> = Mutex::new(BTreeMap::new());
+
+/// Contents of a file held until the game exits
+pub struct Held {
+    pub data: Vec<u8>,
+    /// What was on disk before the first held write. The game saving over it since is a conflict
+    pub base: Seen,
+}
+
+/// Checks process name or path (with either kind of slashes, as Wine gives Windows paths)
+pub fn is_game_process(name: &str) -> bool {
+    let file = name.rsplit(['/', '\\']).next().unwrap_or(name).trim().to_lowercase();
+    PROCESS_NAMES.contains(&file.as_str())
+}
+
+#[cfg(windows)]
+fn process_names() -> Vec<String> {
+    use windows_sys::Win32::Foundation::{CloseHandle, INVALID_HANDLE_VALUE};
+    use windows_sys::Win32::System::Diagnostics::ToolHelp::{CreateToolhelp32Snapshot, Process32FirstW, Process32NextW, PROCESSENTRY32W, TH32CS_SNAPPROCESS};
+
+    let mut names = Vec::new();
+    unsafe {
+        let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0);
+        if snapshot == INVALID_HANDLE_VALUE {
+            return names;
+        }
+        let mut entry: PROCESSENTRY32W = std::mem::zeroed();
+        entry.dwSize = std::mem::size_of::<PROCESSENTRY32W>() as u32;
+        let mut ok = Process32FirstW(snapshot, &mut entry) != 0;
+        while ok {
+            let len = entry.szExeFile.iter().position(|c| *c == 0).unwrap_or(entry.szExeFile.len());
+            names.push(String::from_utf16_lossy(&entry.szExeFile[..len]));
+            ok = Process32NextW(snapshot, &mut entry) != 0;
+        }
+        CloseHandle(snapshot);
+    }
+    names
+}
+
+/// Names and first arguments of all processes. Games started through Wine or Proton show up with their .exe there
+#[cfg(target_os = "linux")]
+fn process_names() -> Vec<String> {
+    let Ok(dir) = std::fs::read_dir("/proc") else { return Vec::new() };
+    dir.flatten()
+        .filter(|e| e.file_name().to_string_lossy().bytes().all(|b| b.is_ascii_digit()))
+        .flat_map(|e| {
+            let comm = std::fs::read_to_string(e.path().join("comm")).unwrap_or_default();
+            let cmdline = std::fs::read(e.path().join("cmdline")).unwrap_or_default();
+            let first = cmdline.split(|b| *b == 0).next().map(|a| String::from_utf8_lossy(a).into_owned()).unwrap_or_default();
+            [comm.trim().to_owned(), first]
+        })
+        .collect()
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+fn process_names() -> Vec<String> {
+    Vec::new()
+}
+
+/// Scans processes right now
+pub fn scan() -> bool {
+    process_names().iter().any(|n| is_game_process(n))
+}
+
+/// Result of the last scan of the watcher
+pub fn is_running() -> bool {
+    RUNNING.load(Ordering::Acquire)
+}
+
+/// Whether writes are held now: the game runs and holding is enabled in config
+pub fn holds_writes() -> bool {
+    is_running() && crate::config::CONFIG.lock().defer_writes
+}
+
+/// Holds the file until the game exits. Only the first hold of a file sets its `base`
+pub fn hold(path: PathBuf, data: Vec<u8>, base: Seen) {
+    PENDING.lock().entry(path).and_modify(|h| h.data.clone_from(&data)).or_insert(Held { data, base });
+}
+
+/// Forgets the held contents of a file written directly
+pub fn release(path: &Path) {
+    PENDING.lock().remove(path);
+}
+
+/// Held contents of the file, if there are any
+pub fn held(path: &Path) -> Option<Vec<u8>> {
+    PENDING.lock().get(path).map(|h| h.data.clone())
+}
+
+/// Amount of held files
+pub fn pending() -> usize {
+    PENDING.lock().len()
+}
+
+/// Outcome of [`flush`]
+#[derive(Debug, Default)]
+pub struct Flushed {
+    pub written: usize,
+    /// Files which couldn't be written with the reason. They stay held until the game exits next time
+    pub failed: Vec<(PathBuf, anyhow::Error)>,
+}
+
+/// Writes all held files, except those the game changed, see [`crate::file_guard::write_held`]
+pub fn flush() -> Flushed {
+    let pending = std::mem::take(&mut *PENDING.lock());
+    let mut res = Flushed::default();
+    for (path, held) in pending {
+        match crate::file_guard::write_held(&path, &held) {
+            Ok(written) => res.written += written as usize,
+            Err(e) => {
+                // A write held meanwhile keeps its contents, but the game may have saved over the older base
+                PENDING.lock().entry(path.clone()).and_modify(|h| h.base = held.base).or_insert(held);
+                res.failed.push((path, e));
+            },
+        }
+    }
+    res
+}
+
+/// Scans processes in background and calls `update` after every scan with the current state.
+/// Held writes are flushed when the game exits, result of that is passed along
+pub fn start_watching(update: impl Fn(bool, Option<Flushed>) + Send + 'static) {
+    std::thread::spawn(move || loop {
+        let running = scan();
+        let exited = RUNNING.swap(running, Ordering::AcqRel) && !running;
+        update(running, exited.then(flush));
+        std::thread::sleep(POLL_INTERVAL);
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn recognizes_game_processes() {
+        assert!(is_game_process("FNaF_World.exe"));
+        assert!(is_game_process("Z:\\home\\user\\Games\\FNaF World\\FNaF World.exe"));
+        assert!(is_game_process("/home/user/.wine/drive_c/fnaf_world.exe"));
+        assert!(!is_game_process("fnaf_save_editor.exe"));
+        assert!(!is_game_process("wine64-preloader"));
+    }
+}
@@ -0,0 +1,199 @@
+//! Append-only journal of every change written into saves and trophies, one JSON line per raw key.
+//! Entries are numbered by their line in `journal.jsonl` of the config dir, starting from 1
+
+use std::io::Write as _;
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+use derive_more::Display;
+use serde::{Deserialize, Serialize};
+use crate::tokenizer::{InfoData, SaveData};
+use crate::Game as FnafWorldGame;
+
+/// Entries sent to the ui
+pub const SHOWN_ENTRIES: usize = 500;
+
+/// What made the change
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, Display)]
+#[serde(rename_all = "snake_case")]
+pub enum Source {
+    #[default]
+    #[display("GUI")]
+    Gui,
+    #[display("CLI")]
+    Cli,
+    #[display("script")]
+    Script,
+    #[display("pins")]
+    Pins,
+    #[display("API")]
+    Api,
+    /// Replay or revert of other entries
+    #[display("journal")]
+    Journal,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Entry {
+    /// Unix time
+    pub time: u64,
+    pub source: Source,
+    #[serde(with = "crate::export::game_name")]
+    pub game: FnafWorldGame,
+    /// Starting from 1, none for trophies
+    pub slot: Option<u8>,
+    pub key: String,
+    /// None if the key wasn't there
+    pub old: Option<u32>,
+    /// None if the key was removed
+    pub new: Option<u32>,
+}
+
+impl Entry {
+    /// `world:2` or `world:info`
+    pub fn target(&self) -> String {
+        let game = crate::export::game_to_str(self.game);
+        match self.slot {
+            Some(slot) => format!("{game}:{slot}"),
+            None => format!("{game}:info"),
+        }
+    }
+}
+
+fn value(v: Option<u32>) -> String {
+    v.map_or_else(|| "-".to_owned(), |v| v.to_string())
+}
+
+impl std::fmt::Display for Entry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} {} {}: {} -> {}",
+            crate::timestamp::format_seconds(self.time),
+            self.source,
+            self.target(),
+            self.key,
+            value(self.old),
+            value(self.new)
+        )
+    }
+}
+
+pub fn journal_path() -> PathBuf {
+    crate::config::config_dir().join("journal.jsonl")
+}
+
+/// Journals every change written by this process from now on. A journal which can't be written is only reported,
+/// the write itself already happened
+pub fn start() {
+    crate::changes::subscribe(|entries| {
+        if let Err(e) = append(entries) {
+            eprintln!("Can't write the journal: {e:#}");
+        }
+    });
+}
+
+fn append(entries: &[Entry]) -> anyhow::Result<()> {
+    let mut lines = String::new();
+    for entry in entries {
+        lines += &serde_json::to_string(entry)?;
+        lines.push('\n');
+    }
+    std::fs::create_dir_all(crate::config::config_dir())?;
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(journal_path())?;
+    file.write_all(lines.as_bytes())?;
+    Ok(())
+}
+
+/// Every entry. Lines which can't be read (e.g. cut off by a crash) are kept as none, so numbers don't shift
+pub fn read() -> anyhow::Result<Vec<Option<Entry>>> {
+    let data = match std::fs::read_to_string(journal_path()) {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    Ok(parse(&data))
+}
+
+fn parse(data: &str) -> Vec<Option<Entry>> {
+    data.lines().map(|line| serde_json::from_str(line).ok()).collect()
+}
+
+/// Values to write into each file: slot or trophies and every changed key
+type Plan = Vec<(FnafWorldGame, Option<u8>, Vec<(String, Option<u32>)>)>;
+
+/// Values of keys after entries in `range`, or before them when reverting
+fn plan(entries: &[Option<Entry>], range: RangeInclusive<usize>, revert: bool) -> anyhow::Result<Plan> {
+    if *range.start() == 0 || range.start() > range.end() || *range.end() > entries.len() {
+        anyhow::bail!("Entries {}-{} are not in the journal, it has {}", range.start(), range.end(), entries.len());
+    }
+    let mut selected = entries[range.start() - 1..*range.end()].iter().flatten().collect::<Vec<_>>();
+    if revert {
+        selected.reverse();
+    }
+    let mut plan = Plan::new();
+    for e in selected {
+        let value = (e.key.clone(), if revert { e.old } else { e.new });
+        match plan.iter_mut().find(|(game, slot, _)| *game == e.game && *slot == e.slot) {
+            Some((_, _, keys)) => keys.push(value),
+            None => plan.push((e.game, e.slot, vec![value])),
+        }
+    }
+    Ok(plan)
+}
+
+/// Writes values of entries `range` (starting from 1) again, or puts back values they replaced.
+/// Returns amount of changed files
+pub fn apply(range: RangeInclusive<usize>, revert: bool) -> anyhow::Result<usize> {
+    let files = plan(&read()?, range, revert)?;
+    for (game, slot, keys) in &files {
+        let keys = keys.iter().map(|(k, v)| (k.as_str(), *v)).collect::<Vec<_>>();
+        match slot {
+            Some(slot) => {
+                let mut save = SaveData::read(*game, *slot)?;
+                save.raw_set_all(&keys)?;
+                save.save(Source::Journal)?;
+            },
+            None => {
+                let mut info = InfoData::read(*game)?;
+                info.raw_set_all(&keys)?;
+                info.save(Source::Journal)?;
+            },
+        }
+    }
+    Ok(files.len())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn plans_replay_and_revert() {
+        let line = |slot: Option<u8>, key: &str, old: Option<u32>, new: Option<u32>| {
+            let e = Entry { time: 0, source: Source::Cli, game: FnafWorldGame::WorldVanilla, slot, key: key.to_owned(), old, new };
+            serde_json::to_string(&e).unwrap()
+        };
+        let data = [
+            line(Some(1), "tokens", Some(5), Some(10)),
+            line(Some(1), "tokens", Some(10), Some(20)),
+            "{\"cut off".to_owned(),
+            line(None, "fan", None, Some(1)),
+        ].join("\n");
+        let entries = parse(&data);
+        assert_eq!(entries.len(), 4);
+        assert!(entries[2].is_none());
+        assert_eq!(entries[3].as_ref().unwrap().to_string(), "1970-01-01 00:00:00 CLI world:info fan: - -> 1");
+
+        let replay = plan(&entries, 1..=4, false).unwrap();
+        assert_eq!(replay.len(), 2);
+        assert_eq!(replay[0].1, Some(1));
+        assert_eq!(replay[0].2.last(), Some(&("tokens".to_owned(), Some(20))));
+        assert_eq!(replay[1].2, [("fan".to_owned(), Some(1))]);
+
+        let revert = plan(&entries, 1..=2, true).unwrap();
+        assert_eq!(revert[0].2.last(), Some(&("tokens".to_owned(), Some(5))));
+
+        assert!(plan(&entries, 0..=2, false).is_err());
+        assert!(plan(&entries, 3..=5, false).is_err());
+    }
+}
@@ -0,0 +1,197 @@
+//! Named copies of saves kept by the editor in `library` folder of the config dir.
+//! Every entry is a raw save file `<id>.sav` with its description in `<id>.toml`
+
+use std::cell::Cell;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use crate::file_guard::{self, Seen};
+use crate::journal::Source;
+use crate::tokenizer::{construct_path, SaveData};
+use crate::Game as FnafWorldGame;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LibraryEntry {
+    /// File name without extension
+    #[serde(skip)]
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub notes: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Game the save was taken from
+    #[serde(with = "crate::export::game_name")]
+    pub game: FnafWorldGame,
+    /// Unix time when it was stored
+    pub created: u64,
+}
+
+impl LibraryEntry {
+    /// Case insensitive search in name, notes and tags
+    pub fn matches(&self, query: &str) -> bool {
+        let query = query.trim().to_lowercase();
+        [&self.name, &self.notes].into_iter().chain(&self.tags).any(|s| s.to_lowercase().contains(&query))
+    }
+}
+
+/// Splits comma separated tags
+pub fn parse_tags(s: &str) -> Vec<String> {
+    s.split(',').map(str::trim).filter(|t| !t.is_empty()).map(str::to_owned).collect()
+}
+
+pub struct Library {
+    dir: PathBuf,
+}
+
+impl Default for Library {
+    fn default() -> Self {
+        Self::new(crate::config::config_dir().join("library"))
+    }
+}
+
+impl Library {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    fn meta_path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{id}.toml"))
+    }
+
+    fn save_path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{id}.sav"))
+    }
+
+    /// Id from the name which is safe for a file name and not taken yet
+    fn free_id(&self, name: &str) -> String {
+        let base = name.chars()
+            .map(|c| if c.is_alphanumeric() { c.to_ascii_lowercase() } else { '-' })
+            .collect::<String>()
+            .split('-').filter(|p| !p.is_empty()).collect::<Vec<_>>().join("-");
+        let base = if base.is_empty() { "save".to_owned() } else { base };
+        let mut id = base.clone();
+        for i in 2.. {
+            if !self.meta_path(&id).exists() {
+                break;
+            }
+            id = format!("{base}-{i}");
+        }
+        id
+    }
+
+    /// Stores contents of a save file. Fails if it isn't a readable save
+    pub fn store(&self, name: &str, notes: &str, tags: Vec<String>, game: FnafWorldGame, data: &[u8]) -> anyhow::Result<LibraryEntry> {
+        crate::save_parser::fnaf_world_parser(std::str::from_utf8(data)?)?;
+        std::fs::create_dir_all(&self.dir)?;
+        let entry = LibraryEntry {
+            id: self.free_id(name),
+            name: name.trim().to_owned(),
+            notes: notes.to_owned(),
+            tags,
+            game,
+            created: crate::timestamp::now(),
+        };
+        std::fs::write(self.save_path(&entry.id), data)?;
+        self.update(&entry)?;
+        Ok(entry)
+    }
+
+    /// Stores the save from a slot (starting from 1)
+    pub fn store_slot(&self, name: &str, notes: &str, tags: Vec<String>, game: FnafWorldGame, slot: u8) -> anyhow::Result<LibraryEntry> {
+        let path = construct_path(game, slot.wrapping_sub(1))?;
+        if !path.exists() {
+            anyhow::bail!("Slot {slot} is empty");
+        }
+        self.store(name, notes, tags, game, &std::fs::read(path)?)
+    }
+
+    /// Writes new name, notes and tags of the entry
+    pub fn update(&self, entry: &LibraryEntry) -> anyhow::Result<()> {
+        std::fs::write(self.meta_path(&entry.id), toml::to_string_pretty(entry)?)?;
+        Ok(())
+    }
+
+    /// All entries, newest first. Entries with broken description are skipped
+    pub fn list(&self) -> anyhow::Result<Vec<LibraryEntry>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+        let mut entries = Vec::new();
+        for file in std::fs::read_dir(&self.dir)? {
+            let path = file?.path();
+            if path.extension().is_none_or(|e| e != "toml") {
+                continue;
+            }
+            let Some(id) = path.file_stem().map(|s| s.to_string_lossy().into_owned()) else { continue };
+            match std::fs::read_to_string(&path).map_err(anyhow::Error::from).and_then(|s| Ok(toml::from_str::<LibraryEntry>(&s)?)) {
+                Ok(entry) => entries.push(LibraryEntry { id, ..entry }),
+                Err(e) => eprintln!("Skipping {}: {e:#}", path.display()),
+            }
+        }
+        entries.sort_by(|a, b| b.created.cmp(&a.created).then_with(|| a.name.cmp(&b.name)));
+        Ok(entries)
+    }
+
+    /// Save of the entry placed into the game it was taken from
+    pub fn load(&self, entry: &LibraryEntry) -> anyhow::Result<SaveData> {
+        let mut save = SaveData::read_path(&self.save_path(&entry.id))?;
+        save.relocate(entry.game, 1)?;
+        Ok(save)
+    }
+
+    /// Copies the entry into a slot (starting from 1). Save which was there is backed up first, its copy is returned
+    pub fn activate(&self, entry: &LibraryEntry, game: FnafWorldGame, slot: u8, source: Source) -> anyhow::Result<Option<PathBuf>> {
+        let data = std::fs::read(self.save_path(&entry.id))?;
+        let target = construct_path(game, slot.wrapping_sub(1))?;
+        let backup = if target.exists() { Some(crate::config::backup(&target)?) } else { None };
+        let old = crate::changes::keys_of(game, Some(slot));
+        file_guard::write_bytes(target, data, &Cell::new(Seen::Unknown))?;
+        crate::changes::written_file(source, game, Some(slot), &old);
+        Ok(backup)
+    }
+
+    pub fn remove(&self, entry: &LibraryEntry) -> anyhow::Result<()> {
+        std::fs::remove_file(self.meta_path(&entry.id))?;
+        remove_if_exists(&self.save_path(&entry.id))
+    }
+}
+
+fn remove_if_exists(path: &Path) -> anyhow::Result<()> {
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn stores_lists_and_removes() {
+        let dir = std::env::temp_dir().join(format!("fnaf_library_test_{}", std::process::id()));
+        let library = Library::new(dir.clone());
+        let data = b"[fnafw]\n1have=1\n1lv=30\ntokens=500";
+        let first = library.store("Pre-Chipper", "", parse_tags("any%, hard"), FnafWorldGame::WorldVanilla, data).unwrap();
+        let mut second = library.store("Pre Chipper", "before the boss", Vec::new(), FnafWorldGame::WorldRefreshed, data).unwrap();
+        assert_eq!(first.id, "pre-chipper");
+        assert_eq!(second.id, "pre-chipper-2");
+        assert!(library.store("Broken", "", Vec::new(), FnafWorldGame::WorldVanilla, b"\xff").is_err());
+
+        second.tags = vec!["glitchless".to_owned()];
+        library.update(&second).unwrap();
+        let entries = library.list().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(entries.iter().any(|e| e.id == "pre-chipper-2" && e.tags == ["glitchless"]));
+        assert_eq!(entries.iter().filter(|e| e.matches("HARD")).count(), 1);
+        assert_eq!(entries.iter().filter(|e| e.matches("boss")).count(), 1);
+
+        let save = library.load(&first).unwrap();
+        assert_eq!(save.tokens, 500);
+        assert_eq!(save.game(), FnafWorldGame::WorldVanilla);
+
+        library.remove(&first).unwrap();
+        assert_eq!(library.list().unwrap().len(), 1);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,124 @@
+//! Named teams (party, chips and bytes) which can be put into any save
+
+use serde::{Deserialize, Serialize};
+use crate::catalog::{self, Kind};
+use crate::tokenizer::SaveData;
+use crate::Game as FnafWorldGame;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Loadout {
+    pub name: String,
+    pub party: [u32; 8],
+    pub chips: [u32; 4],
+    pub bytes: [u32; 4],
+}
+
+/// What happened to items of a loadout the save didn't own
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ApplyReport {
+    pub unlocked: Vec<(Kind, u32)>,
+    /// Left empty in the team. Either unlocking wasn't asked or the item doesn't exist in the game
+    pub removed: Vec<(Kind, u32)>,
+}
+
+impl ApplyReport {
+    pub fn summary(&self) -> String {
+        let names = |items: &[(Kind, u32)]| items.iter().map(|(k, id)| catalog::name(*k, *id)).collect::<Vec<_>>().join(", ");
+        match (self.unlocked.is_empty(), self.removed.is_empty()) {
+            (true, true) => "Applied".to_owned(),
+            (false, true) => format!("Applied. Unlocked {}", names(&self.unlocked)),
+            (true, false) => format!("Applied. Not owned: {}", names(&self.removed)),
+            (false, false) => format!("Applied. Unlocked {}. Not available: {}", names(&self.unlocked), names(&self.removed)),
+        }
+    }
+}
+
+fn exists_in(kind: Kind, id: u32, game: FnafWorldGame) -> bool {
+    catalog::get(kind, id).is_none_or(|e| !e.refreshed_only || game == FnafWorldGame::WorldRefreshed)
+}
+
+impl Loadout {
+    pub fn from_save(name: &str, save: &SaveData) -> Self {
+        Self {
+            name: name.to_owned(),
+            party: save.selected_characters,
+            chips: save.selected_chips,
+            bytes: save.selected_bytes,
+        }
+    }
+
+    /// Puts the team into `save`. Items the save doesn't own are unlocked if `unlock` is set, otherwise their places stay empty
+    pub fn apply(&self, save: &mut SaveData, unlock: bool) -> ApplyReport {
+        let mut report = ApplyReport::default();
+        let game = save.game();
+        let mut owns = |save: &mut SaveData, kind: Kind, id: u32| {
+            let owned = match kind {
+                Kind::Character => save.contains_character(id),
+                Kind::Chip => save.chips.contains(&id),
+                Kind::Byte => save.bytes.contains(&id),
+            };
+            if id == 0 || owned {
+                return true;
+            }
+            if !unlock || !exists_in(kind, id, game) {
+                report.removed.push((kind, id));
+                return false;
+            }
+            match kind {
+                Kind::Character => {
+                    let ch = save.edit_character(id);
+                    ch.lvl = 1;
+                    ch.next = 100;
+                },
+                Kind::Chip => { save.chips.insert(id); },
+                Kind::Byte => { save.bytes.insert(id); },
+            }
+            report.unlocked.push((kind, id));
+            true
+        };
+
+        let party = self.party.map(|id| if owns(save, Kind::Character, id) { id } else { 0 });
+        let chips = self.chips.map(|id| if owns(save, Kind::Chip, id) { id } else { 0 });
+        let bytes = self.bytes.map(|id| if owns(save, Kind::Byte, id) { id } else { 0 });
+        save.selected_characters = party;
+        save.selected_chips = chips;
+        save.selected_bytes = bytes;
+        report
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tokenizer::test_save;
+
+    fn team() -> Loadout {
+        Loadout::from_save("Team", &test_save("[fnafw]\n1have=1\n5have=1\ns1=5\ns2=1\nc2=1\nc23=1\nactive1=2\nactive2=23"))
+    }
+
+    #[test]
+    fn takes_team_from_save() {
+        assert_eq!(team().party[..2], [5, 1]);
+    }
+
+    #[test]
+    fn removes_locked_without_unlocking() {
+        let mut target = test_save("[fnafw]\n1have=1\ns1=1");
+        let report = team().apply(&mut target, false);
+        assert_eq!(target.selected_characters[..2], [0, 1]);
+        assert_eq!(target.selected_chips[..2], [0, 0]);
+        assert_eq!(report.removed, vec![(Kind::Character, 5), (Kind::Chip, 2), (Kind::Chip, 23)]);
+    }
+
+    #[test]
+    fn unlocks_what_the_game_has() {
+        let mut target = test_save("[fnafw]\n1have=1\ns1=1");
+        let report = team().apply(&mut target, true);
+        assert_eq!(target.selected_characters[..2], [5, 1]);
+        assert_eq!(target.characters[&5].lvl, 1);
+        assert_eq!(target.selected_chips[..2], [2, 0]);
+        assert_eq!(report.unlocked, vec![(Kind::Character, 5), (Kind::Chip, 2)]);
+        // Vanilla save: chip 23 exists only in Refreshed
+        assert_eq!(report.removed, vec![(Kind::Chip, 23)]);
+    }
+}
@@ -0,0 +1,79 @@
+//! Named places of the overworld shown on the map.
+//! Coordinates are in save units (`x=`/`y=`). They are rough guesses which only place the marker on the map,
+//! none were checked to land in the area in the game, so they aren't offered as positions to move a save to
+
+use crate::Game as FnafWorldGame;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Guardian {
+    Porkpatch,
+    /// Index in `SaveData::guardians`
+    Button(usize),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Location {
+    pub name: &'static str,
+    pub pos: (u32, u32),
+    /// Index in `SaveData::jumps`
+    pub jump: Option<usize>,
+    pub guardian: Option<Guardian>,
+    /// Index in `SaveData::clocks_found`
+    pub clock: Option<usize>,
+    pub refreshed_only: bool,
+}
+
+const fn loc(name: &'static str, x: u32, y: u32) -> Location {
+    Location { name, pos: (x, y), jump: None, guardian: None, clock: None, refreshed_only: false }
+}
+
+pub const LOCATIONS: &[Location] = &[
+    Location { jump: Some(0), guardian: Some(Guardian::Porkpatch), clock: Some(0), ..loc("Fazbear Hills", 1500, 700) },
+    Location { jump: Some(1), guardian: Some(Guardian::Button(0)), clock: Some(1), ..loc("Choppy's Woods", 1500, 2000) },
+    Location { jump: Some(2), guardian: Some(Guardian::Button(1)), clock: Some(2), ..loc("Dusting Fields", 600, 2600) },
+    Location { jump: Some(3), guardian: Some(Guardian::Button(2)), clock: Some(3), ..loc("Lilygear Lake", 2800, 2400) },
+    Location { jump: Some(4), ..loc("Mysterious Mine", 2000, 3200) },
+    Location { jump: Some(5), guardian: Some(Guardian::Button(3)), clock: Some(4), ..loc("Blacktomb Yard", 3300, 3600) },
+    Location { guardian: Some(Guardian::Button(4)), refreshed_only: true, ..loc("Deep-Metal Mine", 2400, 4300) },
+    Location { jump: Some(6), refreshed_only: true, ..loc("Pinwheel Circus", 700, 4100) },
+    loc("Halloween area", 3600, 900),
+];
+
+/// Locations existing in the game
+pub fn locations(game: FnafWorldGame) -> impl Iterator<Item = &'static Location> {
+    LOCATIONS.iter().filter(move |l| !l.refreshed_only || game == FnafWorldGame::WorldRefreshed)
+}
+
+/// Area covered by the map as (x, y, width, height): all locations with some margin around
+pub fn bounds() -> (u32, u32, u32, u32) {
+    let xs = LOCATIONS.iter().map(|l| l.pos.0);
+    let ys = LOCATIONS.iter().map(|l| l.pos.1);
+    let (min_x, max_x) = (xs.clone().min().unwrap_or(0), xs.max().unwrap_or(0));
+    let (min_y, max_y) = (ys.clone().min().unwrap_or(0), ys.max().unwrap_or(0));
+    let margin_x = (max_x - min_x) / 8;
+    let margin_y = (max_y - min_y) / 8;
+    let (x, y) = (min_x.saturating_sub(margin_x), min_y.saturating_sub(margin_y));
+    (x, y, max_x + margin_x - x, max_y + margin_y - y)
+}
+
+/// Closest location to `pos` and distance to it
+pub fn nearest(game: FnafWorldGame, pos: (u32, u32)) -> Option<(&'static Location, f64)> {
+    locations(game)
+        .map(|l| (l, (l.pos.0 as f64 - pos.0 as f64).hypot(l.pos.1 as f64 - pos.1 as f64)))
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn finds_nearest() {
+        let (l, _) = nearest(FnafWorldGame::WorldVanilla, (1450, 1900)).unwrap();
+        assert_eq!(l.name, "Choppy's Woods");
+        assert!(locations(FnafWorldGame::WorldVanilla).all(|l| !l.refreshed_only));
+
+        let (x, y, w, h) = bounds();
+        assert!(LOCATIONS.iter().all(|l| (x..x + w).contains(&l.pos.0) && (y..y + h).contains(&l.pos.1)));
+    }
+}
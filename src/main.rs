@@ -2,15 +2,45 @@
 #[cfg(not(target_os = "windows"))]
 compile_error!("Program should be compiled for windows only!");
 
-use std::{sync::{atomic::AtomicBool, LazyLock}, time::Duration};
+use std::{sync::{atomic::AtomicBool, Arc, LazyLock}, time::Duration};
 use parking_lot::Mutex;
-use slint::{Timer, VecModel, Weak};
+use slint::{Model, Timer, VecModel, Weak};
+use crate::journal::Source;
 use crate::tokenizer::{InfoData, SaveData, Token};
 
 mod save_parser;
 mod tokenizer;
 mod result_ext;
-// mod save_file_watcher;
+mod export;
+mod diff;
+mod cli;
+mod merge;
+mod progress;
+mod catalog;
+mod validate;
+mod locations;
+mod armor;
+mod config;
+mod loadout;
+mod randomize;
+mod share;
+mod bundle;
+mod timestamp;
+mod library;
+mod quickstate;
+mod game_process;
+mod file_guard;
+mod pins;
+mod save_file_watcher;
+mod discovery;
+mod corpus;
+mod script;
+mod journal;
+mod changes;
+mod api;
+mod overlay;
+mod report;
+mod settings;
 
 use result_ext::ResultExt as _;
 use mimalloc::MiMalloc;
@@ -18,14 +48,22 @@ use mimalloc::MiMalloc;
 #[global_allocator]
 static GLOBAL: MiMalloc = MiMalloc;
 
-static SAVE1: LazyLock<Mutex<SaveData>> = LazyLock::new(|| Mutex::new(SaveData::read(Game::WorldVanilla, 1).unwrap_or_default()));
-static FW_INFO: LazyLock<Mutex<InfoData>> = LazyLock::new(|| Mutex::new(InfoData::read(Game::WorldVanilla).unwrap_or_default()));
+static SAVE1: LazyLock<Mutex<SaveData>> = LazyLock::new(|| {
+    let (game, slot) = start_slot();
+    Mutex::new(SaveData::read(game, slot).unwrap_or_default())
+});
+static FW_INFO: LazyLock<Mutex<InfoData>> = LazyLock::new(|| Mutex::new(InfoData::read(start_slot().0).unwrap_or_default()));
 static REJECT_SAVE: AtomicBool = AtomicBool::new(false);
 
 
 slint::include_modules!();
 
 fn main() {
+    journal::start();
+    if std::env::args_os().len() > 1 {
+        std::process::exit(cli::run());
+    }
+
     let ui = MainWindow::new().unwrap();
     
     let ui_weak = ui.as_weak();
@@ -42,8 +80,11 @@ fn main() {
         timer.start(slint::TimerMode::SingleShot, dur, move || ui_weak.unwrap().invoke_report_close());
     });
 
+    watch_slot_summaries(&ui);
+    update_slot_summaries(ui.as_weak());
     let ui_weak = ui.as_weak();
     ui.on_slot_changed(move |game, slot| {
+        update_slot_summaries(ui_weak.clone());
         match game {
             Game::WorldRefreshed | Game::WorldVanilla => {
                 if slot == 0 {
@@ -61,6 +102,28 @@ fn main() {
     register_callbacks_chips_screen(&ui);
     register_callbacks_bytes_screen(&ui);
     register_callbacks_trophy_scr(&ui);
+    register_callbacks_export(&ui);
+    register_callbacks_compare(&ui);
+    register_callbacks_merge(&ui);
+    register_callbacks_progress(&ui);
+    register_callbacks_catalog(&ui);
+    register_callbacks_map(&ui);
+    register_callbacks_loadouts(&ui);
+    register_callbacks_randomizer(&ui);
+    register_callbacks_share(&ui);
+    register_callbacks_bundle(&ui);
+    register_callbacks_library(&ui);
+    register_callbacks_practice(&ui);
+    register_callbacks_game_process(&ui);
+    register_callbacks_file_guard(&ui);
+    register_callbacks_pins(&ui);
+    register_callbacks_discovery(&ui);
+    register_callbacks_script(&ui);
+    register_callbacks_journal(&ui);
+    register_callbacks_api(&ui);
+    register_callbacks_overlay(&ui);
+    register_callbacks_settings(&ui);
+    restore_session(&ui);
     
     // Start a watcher which will update data in editor if save file change from outside (game itself for example)
     // start_watching(ui.as_weak());
@@ -69,13 +132,13 @@ fn main() {
     ui.on_lvl_edited(move |id, lvl| {
         let mut save = SAVE1.lock();
         save.edit_character(id as u32).lvl = lvl as u32;
-        save.save().report_to_user(ui_weak.clone())
+        save.save(Source::Gui).report_to_user(ui_weak.clone())
     });
     let ui_weak = ui.as_weak();
     ui.on_next_edited(move |id, next| {
         let mut save = SAVE1.lock();
         save.edit_character(id as u32).next = next as u32;
-        save.save().report_to_user(ui_weak.clone())
+        save.save(Source::Gui).report_to_user(ui_weak.clone())
     });
     let ui_weak = ui.as_weak();
     ui.on_have_edited(move |id| {
@@ -89,7 +152,7 @@ fn main() {
             ch.lvl = 1;
             ch.next = 100;
         }
-        save.save().report_to_user(ui_weak.clone())
+        save.save(Source::Gui).report_to_user(ui_weak.clone())
     });
     
     
@@ -103,87 +166,101 @@ fn register_callbacks_world_screen(ui: &MainWindow) {
     ui.on_gamemode_edited(move |gm| {
         let mut save = SAVE1.lock();
         save.mode = gm.into();
-        save.save().report_to_user(ui_weak.clone())
+        save.save(Source::Gui).report_to_user(ui_weak.clone())
     });
     let ui_weak = ui.as_weak();
     ui.on_other_edited(move |diff| {
         let mut save = SAVE1.lock();
         save.diff = diff.into();
-        save.save().report_to_user(ui_weak.clone())
+        save.save(Source::Gui).report_to_user(ui_weak.clone())
     });
     let ui_weak = ui.as_weak();
     ui.on_hours_edited(move |hour| {
         let mut save = SAVE1.lock();
         save.time.0 = hour as u32;
-        save.save().report_to_user(ui_weak.clone())
+        save.save(Source::Gui).report_to_user(ui_weak.clone())
     });
     let ui_weak = ui.as_weak();
     ui.on_minutes_edited(move |minutes| {
         let mut save = SAVE1.lock();
         save.time.1 = minutes as u32;
-        save.save().report_to_user(ui_weak.clone())
+        save.save(Source::Gui).report_to_user(ui_weak.clone())
     });
     let ui_weak = ui.as_weak();
     ui.on_seconds_edited(move |seconds| {
         let mut save = SAVE1.lock();
         save.time.2 = seconds as u32;
-        save.save().report_to_user(ui_weak.clone())
+        save.save(Source::Gui).report_to_user(ui_weak.clone())
     });
     let ui_weak = ui.as_weak();
     ui.on_x_edited(move |x| {
         let mut save = SAVE1.lock();
         save.save_pos.0 = x as u32;
-        save.save().report_to_user(ui_weak.clone())
+        save.save(Source::Gui).report_to_user(ui_weak.clone())
     });
     let ui_weak = ui.as_weak();
     ui.on_y_edited(move |y| {
         let mut save = SAVE1.lock();
         save.save_pos.1 = y as u32;
-        save.save().report_to_user(ui_weak.clone())
+        save.save(Source::Gui).report_to_user(ui_weak.clone())
     });
     let ui_weak = ui.as_weak();
     ui.on_tokens_edited(move |tokens| {
         let mut save = SAVE1.lock();
         save.tokens = tokens as u32;
-        save.save().report_to_user(ui_weak.clone())
+        save.save(Source::Gui).report_to_user(ui_weak.clone())
     });
     let ui_weak = ui.as_weak();
     ui.on_pearls_edited(move |pearls| {
         let mut save = SAVE1.lock();
         save.pearls = pearls as u32;
-        save.save().report_to_user(ui_weak.clone())
+        save.save(Source::Gui).report_to_user(ui_weak.clone())
     });
     let ui_weak = ui.as_weak();
-    ui.on_armor_edited(move |armor| {
+    ui.on_armor_defence_edited(move |defence| {
         let mut save = SAVE1.lock();
-        save.armor_id = armor as u32;
-        save.armor = if armor < 3 { armor as u32 } else { 10 };
-        save.save().report_to_user(ui_weak.clone())
+        save.armor = defence as u32;
+        ui_weak.unwrap().set_armor_consistent(armor::is_consistent(&save.armor_owned, save.armor));
+        save.save(Source::Gui).report_to_user(ui_weak.clone())
     });
     let ui_weak = ui.as_weak();
+    ui.on_armor_owned_edited(move |idx, val| {
+        let Some(piece) = armor::PIECES.get(idx as usize) else { return };
+        let mut save = SAVE1.lock();
+        if val {
+            save.armor_owned.insert(piece.id);
+        }
+        else {
+            save.armor_owned.remove(&piece.id);
+        }
+        ui_weak.unwrap().set_armor_consistent(armor::is_consistent(&save.armor_owned, save.armor));
+        save.save(Source::Gui).report_to_user(ui_weak.clone())
+    });
+    let global = ui.global::<ArmorPieces>();
+    global.set_defences(std::rc::Rc::new(VecModel::from(armor::PIECES.iter().map(|p| p.defence as i32).collect::<Vec<_>>())).into());
+    let ui_weak = ui.as_weak();
     ui.on_jumps_edited(move |idx, val| {
         let mut save = SAVE1.lock();
         save.jumps[idx as usize] = val;
-        save.save().report_to_user(ui_weak.clone())
+        save.save(Source::Gui).report_to_user(ui_weak.clone())
     });
     let ui_weak = ui.as_weak();
     ui.on_guardians_edited(move |idx, val| {
         let mut save = SAVE1.lock();
-        println!("idx={idx}, val={val}");
         save.guardians[idx as usize] = val;
-        save.save().report_to_user(ui_weak.clone())
+        save.save(Source::Gui).report_to_user(ui_weak.clone())
     });
     let ui_weak = ui.as_weak();
     ui.on_clocks_edited(move |idx, val| {
         let mut save = SAVE1.lock();
         save.clocks_found[idx as usize] = val;
-        save.save().report_to_user(ui_weak.clone())
+        save.save(Source::Gui).report_to_user(ui_weak.clone())
     });
     let ui_weak = ui.as_weak();
     ui.on_porkpatch_edited(move |value| {
         let mut save = SAVE1.lock();
         save.porkpatch_button = value;
-        save.save().report_to_user(ui_weak.clone())
+        save.save(Source::Gui).report_to_user(ui_weak.clone())
     });
     let ui_weak = ui.as_weak();
     ui.on_key_edited(move |key| {
@@ -194,7 +271,7 @@ fn register_callbacks_world_screen(ui: &MainWindow) {
         else {
             save.flags.remove(&Token::Key);
         }
-        save.save().report_to_user(ui_weak.clone())
+        save.save(Source::Gui).report_to_user(ui_weak.clone())
     });
     let ui_weak = ui.as_weak();
     ui.on_portal_edited(move |portal| {
@@ -205,7 +282,7 @@ fn register_callbacks_world_screen(ui: &MainWindow) {
         else {
             save.flags.remove(&Token::Portal);
         }
-        save.save().report_to_user(ui_weak.clone())
+        save.save(Source::Gui).report_to_user(ui_weak.clone())
     });
     let ui_weak = ui.as_weak();
     ui.on_lantern_edited(move |lantern| {
@@ -216,7 +293,7 @@ fn register_callbacks_world_screen(ui: &MainWindow) {
         else {
             save.flags.remove(&Token::Lantern);
         }
-        save.save().report_to_user(ui_weak.clone())
+        save.save(Source::Gui).report_to_user(ui_weak.clone())
     });
 }
 
@@ -231,7 +308,7 @@ fn register_callbacks_chips_screen(ui: &MainWindow) {
         else {
             save.chips.remove(&(id as u32 + 1));
         }
-        save.save().report_to_user(ui_weak.clone());
+        save.save(Source::Gui).report_to_user(ui_weak.clone());
     });
     let ui_weak = ui.as_weak();
     ui.on_chip_selected_edited(move |id, val| {
@@ -242,7 +319,7 @@ fn register_callbacks_chips_screen(ui: &MainWindow) {
         else {
             save.remove_selected_chip(id as u32 + 1);
         }
-        save.save().report_to_user(ui_weak.clone());
+        save.save(Source::Gui).report_to_user(ui_weak.clone());
     });
 }
 
@@ -257,7 +334,7 @@ fn register_callbacks_bytes_screen(ui: &MainWindow) {
         else {
             save.bytes.remove(&(id as u32 + 1));
         }
-        save.save().report_to_user(ui_weak.clone());
+        save.save(Source::Gui).report_to_user(ui_weak.clone());
     });
     let ui_weak = ui.as_weak();
     ui.on_byte_selected_edited(move |id, val| {
@@ -268,7 +345,7 @@ fn register_callbacks_bytes_screen(ui: &MainWindow) {
         else {
             save.remove_selected_byte(id as u32 + 1);
         }
-        save.save().report_to_user(ui_weak.clone());
+        save.save(Source::Gui).report_to_user(ui_weak.clone());
     });
 }
 
@@ -283,28 +360,1309 @@ fn register_callbacks_trophy_scr(ui: &MainWindow) {
         else {
             info.endings.remove(&(id as u32));
         }
-        info.save().report_to_user(ui_weak.clone());
+        info.save(Source::Gui).report_to_user(ui_weak.clone());
     });
     let ui_weak = ui.as_weak();
     ui.on_trophy_fan_edited(move |val| {
         let mut info = FW_INFO.lock();
         info.fan = val;
-        info.save().report_to_user(ui_weak.clone());
+        info.save(Source::Gui).report_to_user(ui_weak.clone());
     });
     let ui_weak = ui.as_weak();
     ui.on_trophy_pearl_edited(move |val| {
         let mut info = FW_INFO.lock();
         info.pearl = val;
-        info.save().report_to_user(ui_weak.clone());
+        info.save(Source::Gui).report_to_user(ui_weak.clone());
+    });
+}
+
+/// Registers callbacks for export and import of the current save
+fn register_callbacks_export(ui: &MainWindow) {
+    let ui_weak = ui.as_weak();
+    ui.on_export_save(move || {
+        let save = SAVE1.lock().clone();
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("JSON", &["json"])
+            .add_filter("TOML", &["toml"])
+            .set_file_name(format!("{}_{}.json", export::game_to_str(save.game()), save.slot()))
+            .save_file()
+        else { return };
+        let info = InfoData::read(save.game()).ok();
+        export::SaveExport::new(&save, info.as_ref()).write(&path).report_to_user(ui_weak.clone());
+    });
+    let ui_weak = ui.as_weak();
+    ui.on_import_save(move || {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("Save export", &["json", "toml"])
+            .pick_file()
+        else { return };
+        let (game, slot) = {
+            let save = SAVE1.lock();
+            (save.game(), save.slot())
+        };
+        export::SaveExport::read(&path).and_then(|export| export.into_parts(game, slot)).process_or_report(ui_weak.clone(), |(save, info)| {
+            save.save(Source::Gui).report_to_user(ui_weak.clone());
+            if let Some(info) = info {
+                info.save(Source::Gui).report_to_user(ui_weak.clone());
+                *FW_INFO.lock() = info;
+            }
+            *SAVE1.lock() = save;
+            init_fnaf_world_save_data(ui_weak.clone());
+            update_slot_summaries(ui_weak.clone());
+        });
+    });
+    let ui_weak = ui.as_weak();
+    ui.on_generate_report(move || {
+        let save = SAVE1.lock().clone();
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("HTML", &["html"])
+            .add_filter("Markdown", &["md"])
+            .set_file_name(format!("{}_{}_report.html", export::game_to_str(save.game()), save.slot()))
+            .save_file()
+        else { return };
+        let info = InfoData::read(save.game()).ok();
+        report::write(&save, info.as_ref(), &path).report_to_user(ui_weak.clone());
+    });
+}
+
+/// Game and slot (starting from 1) by their position in slot lists of the ui
+fn slot_by_index(idx: i32) -> Option<(Game, u8)> {
+    match idx {
+        0..=2 => Some((Game::WorldVanilla, idx as u8 + 1)),
+        3..=6 => Some((Game::WorldRefreshed, idx as u8 - 2)),
+        _ => None,
+    }
+}
+
+/// Loads save by its position in slot list or asks user for a file if position is past the slots.
+/// Returns `None` if user cancelled file selection
+fn load_slot_or_file(idx: i32) -> anyhow::Result<Option<SaveData>> {
+    match slot_by_index(idx) {
+        Some((game, slot)) => SaveData::read(game, slot).map(Some),
+        None => match rfd::FileDialog::new().pick_file() {
+            Some(path) => export::load_save_file(&path).map(Some),
+            None => Ok(None),
+        },
+    }
+}
+
+/// Builds rows for side by side table of changes
+fn changes_model(changes: &[diff::Change]) -> slint::ModelRc<slint::ModelRc<slint::StandardListViewItem>> {
+    let rows = changes.iter().map(|c| {
+        let (what, old, new) = c.columns();
+        let row = [what, old, new].map(|s| slint::StandardListViewItem::from(slint::SharedString::from(s)));
+        slint::ModelRc::new(VecModel::from_slice(&row))
+    }).collect::<Vec<_>>();
+    std::rc::Rc::new(VecModel::from(rows)).into()
+}
+
+/// Reloads ui if specified save is the one being edited now
+fn reload_if_current(game: Game, slot: u8, ui_weak: Weak<MainWindow>) {
+    let current = {
+        let save = SAVE1.lock();
+        save.game() == game && save.slot() == slot
+    };
+    if current {
+        load_fnaf_world_save(game, slot, ui_weak);
+    }
+}
+
+fn register_callbacks_compare(ui: &MainWindow) {
+    let ui_weak = ui.as_weak();
+    ui.on_compare_saves(move |left, right| {
+        let res = load_slot_or_file(left).and_then(|l| Ok(l.zip(load_slot_or_file(right)?)));
+        res.process_or_report(ui_weak.clone(), |saves| {
+            let Some((left, right)) = saves else { return };
+            let changes = diff::diff_saves(&left, &right);
+            let summary = match changes.len() {
+                0 => "Saves are identical".to_owned(),
+                n => format!("{n} differences"),
+            };
+            ui_weak.unwrap().invoke_set_compare_result(changes_model(&changes), summary.into());
+        });
+    });
+}
+
+/// Merges saves chosen in merge screen. Returns current target save and merge result placed into target slot
+fn merge_by_options(opts: &MergeOptions) -> anyhow::Result<Option<(SaveData, SaveData)>> {
+    let Some((game, slot)) = slot_by_index(opts.target) else { anyhow::bail!("Wrong target slot") };
+    let Some(first) = load_slot_or_file(opts.first)? else { return Ok(None) };
+    let Some(second) = load_slot_or_file(opts.second)? else { return Ok(None) };
+    let rule = |i: i32| merge::ScalarRule::ALL.get(i as usize).copied().unwrap_or_default();
+    let rules = merge::MergeRules {
+        tokens: rule(opts.tokens),
+        pearls: rule(opts.pearls),
+        time: rule(opts.time),
+        save_pos: rule(opts.save_pos),
+    };
+    let mut merged = merge::merge(&first, &second, &rules);
+    merged.relocate(game, slot)?;
+    Ok(Some((SaveData::read(game, slot)?, merged)))
+}
+
+fn register_callbacks_merge(ui: &MainWindow) {
+    // Merge is written only after it was previewed with the same options
+    static PREVIEW: Mutex<Option<(MergeOptions, SaveData)>> = Mutex::new(None);
+
+    let ui_weak = ui.as_weak();
+    ui.on_preview_merge(move |opts| {
+        merge_by_options(&opts).process_or_report(ui_weak.clone(), |res| {
+            let Some((target, merged)) = res else { return };
+            let changes = diff::diff_saves(&target, &merged);
+            let summary = format!("{} changes. Press \"Merge\" to write them", changes.len());
+            ui_weak.unwrap().invoke_set_merge_preview(changes_model(&changes), summary.into());
+            *PREVIEW.lock() = Some((opts, merged));
+        });
+    });
+    let ui_weak = ui.as_weak();
+    ui.on_apply_merge(move |opts| {
+        let merged = match PREVIEW.lock().take() {
+            Some((preview_opts, merged)) if preview_opts == opts => merged,
+            _ => {
+                ui_weak.unwrap().invoke_report("Preview the merge first".into());
+                return;
+            }
+        };
+        merged.save(Source::Gui).process_or_report(ui_weak.clone(), |_| {
+            ui_weak.unwrap().invoke_set_merge_preview(changes_model(&[]), "Merged".into());
+            reload_if_current(merged.game(), merged.slot(), ui_weak.clone());
+            update_slot_summaries(ui_weak.clone());
+        });
+    });
+}
+
+fn register_callbacks_progress(ui: &MainWindow) {
+    let ui_weak = ui.as_weak();
+    ui.on_progress_requested(move || {
+        let save = SAVE1.lock();
+        let info = InfoData::read(save.game()).ok();
+        let progress = progress::Progress::new(&save, info.as_ref());
+        let entries = progress.categories.iter().map(|c| ProgressEntry {
+            name: match c.detail {
+                Some(detail) => format!("{} ({detail})", c.name).into(),
+                None => c.name.into(),
+            },
+            done: c.done as i32,
+            total: c.total as i32,
+        }).collect::<Vec<_>>();
+        ui_weak.unwrap().invoke_set_progress(std::rc::Rc::new(VecModel::from(entries)).into(), progress.overall());
+    });
+}
+
+impl From<CatalogKind> for catalog::Kind {
+    fn from(value: CatalogKind) -> Self {
+        match value {
+            CatalogKind::Character => Self::Character,
+            CatalogKind::Chip => Self::Chip,
+            CatalogKind::Byte => Self::Byte,
+        }
+    }
+}
+
+/// Fills catalog global with names and descriptions and sets up search
+fn register_callbacks_catalog(ui: &MainWindow) {
+    let global = ui.global::<Catalog>();
+    // Ids missing in catalog get empty strings so arrays can be indexed by id - 1
+    let strings = |kind, f: fn(&catalog::Entry) -> String| {
+        let v = (1..=catalog::max_id(kind))
+            .map(|id| catalog::get(kind, id).map(f).unwrap_or_default().into())
+            .collect::<Vec<slint::SharedString>>();
+        slint::ModelRc::from(std::rc::Rc::new(VecModel::from(v)))
+    };
+    global.set_character_names(strings(catalog::Kind::Character, |e| e.name.clone()));
+    global.set_character_info(strings(catalog::Kind::Character, catalog::Entry::info));
+    global.set_chip_names(strings(catalog::Kind::Chip, |e| e.name.clone()));
+    global.set_chip_info(strings(catalog::Kind::Chip, catalog::Entry::info));
+    global.set_byte_names(strings(catalog::Kind::Byte, |e| e.name.clone()));
+    global.set_byte_info(strings(catalog::Kind::Byte, catalog::Entry::info));
+    global.on_search(|kind, query| {
+        slint::ModelRc::from(std::rc::Rc::new(VecModel::from(catalog::matches(kind.into(), &query))))
+    });
+}
+
+/// Fills world map global with locations
+fn register_callbacks_map(ui: &MainWindow) {
+    let global = ui.global::<WorldMap>();
+    let marker = |l: &locations::Location| MapMarker {
+        name: l.name.into(),
+        x: l.pos.0 as i32,
+        y: l.pos.1 as i32,
+        jump: l.jump.map_or(-1, |j| j as i32),
+        guardian: match l.guardian {
+            Some(locations::Guardian::Porkpatch) => 5,
+            Some(locations::Guardian::Button(i)) => i as i32,
+            None => -1,
+        },
+        clock: l.clock.map_or(-1, |c| c as i32),
+        refreshed_only: l.refreshed_only,
+    };
+    let markers = locations::LOCATIONS.iter().map(marker).collect::<Vec<_>>();
+    let (x, y, width, height) = locations::bounds();
+    global.set_markers(std::rc::Rc::new(VecModel::from(markers)).into());
+    global.set_bounds(MapBounds { x: x as i32, y: y as i32, width: width as i32, height: height as i32 });
+    global.on_nearest(|x, y| {
+        let game = SAVE1.lock().game();
+        locations::nearest(game, (x.max(0) as u32, y.max(0) as u32)).map(|(l, _)| l.name.into()).unwrap_or_default()
+    });
+}
+
+/// Randomizes save chosen in randomizer screen. Returns current target save and the result
+fn randomize_by_options(opts: &RandomizerOptions) -> anyhow::Result<(SaveData, SaveData)> {
+    let Some((game, slot)) = slot_by_index(opts.target) else { anyhow::bail!("Choose a save to randomize") };
+    if opts.seed.trim().is_empty() {
+        anyhow::bail!("Enter a seed or press \"New seed\"");
+    }
+    let rules = randomize::Rules {
+        party: opts.party as usize,
+        levels: opts.min_level as u32..=opts.max_level as u32,
+        chips: opts.chips as usize,
+        bytes: opts.bytes as usize,
+        allowed_chips: randomize::parse_ids(&opts.allowed_chips)?,
+        allowed_bytes: randomize::parse_ids(&opts.allowed_bytes)?,
+        keep_progression: opts.keep_progression,
+    };
+    let target = SaveData::read(game, slot)?;
+    let res = randomize::randomize(&target, randomize::parse_seed(&opts.seed), &rules);
+    Ok((target, res))
+}
+
+fn register_callbacks_randomizer(ui: &MainWindow) {
+    // Same as merge, written only after preview with the same options
+    static PREVIEW: Mutex<Option<(RandomizerOptions, SaveData)>> = Mutex::new(None);
+
+    ui.set_randomizer_seed(randomize::new_seed().into());
+    let ui_weak = ui.as_weak();
+    ui.on_new_seed(move || ui_weak.unwrap().set_randomizer_seed(randomize::new_seed().into()));
+    let ui_weak = ui.as_weak();
+    ui.on_preview_randomize(move |opts| {
+        randomize_by_options(&opts).process_or_report(ui_weak.clone(), |(target, res)| {
+            let changes = diff::diff_saves(&target, &res);
+            let summary = format!("{} changes. Press \"Randomize\" to write them", changes.len());
+            ui_weak.unwrap().invoke_set_randomize_preview(changes_model(&changes), summary.into());
+            *PREVIEW.lock() = Some((opts, res));
+        });
+    });
+    let ui_weak = ui.as_weak();
+    ui.on_apply_randomize(move |opts| {
+        let res = match PREVIEW.lock().take() {
+            Some((preview_opts, res)) if preview_opts == opts => res,
+            _ => {
+                ui_weak.unwrap().invoke_report("Preview the result first".into());
+                return;
+            }
+        };
+        res.save(Source::Gui).process_or_report(ui_weak.clone(), |_| {
+            let summary = format!("Randomized with seed {}", opts.seed);
+            ui_weak.unwrap().invoke_set_randomize_preview(changes_model(&[]), summary.into());
+            reload_if_current(res.game(), res.slot(), ui_weak.clone());
+            update_slot_summaries(ui_weak.clone());
+        });
+    });
+}
+
+fn register_callbacks_script(ui: &MainWindow) {
+    // Script is written only after it was previewed on the same slot
+    static PREVIEW: Mutex<Option<(String, i32, script::SlotRun)>> = Mutex::new(None);
+
+    let ui_weak = ui.as_weak();
+    ui.on_load_script(move || {
+        let Some(path) = rfd::FileDialog::new().add_filter("Rhai script", &["rhai"]).pick_file() else { return };
+        std::fs::read_to_string(path).process_or_report(ui_weak.clone(), |script| ui_weak.unwrap().set_script(script.into()));
+    });
+    let ui_weak = ui.as_weak();
+    ui.on_preview_script(move |script, target| {
+        let Some((game, slot)) = slot_by_index(target) else { return };
+        script::run_on_slot(&script, game, slot).process_or_report(ui_weak.clone(), |run| {
+            let changes = run.changes().cloned().collect::<Vec<_>>();
+            let summary = format!("{} changes. Press \"Write\" to write them", changes.len());
+            let output = run.output.printed.join("\n");
+            ui_weak.unwrap().invoke_set_script_preview(changes_model(&changes), output.into(), summary.into());
+            *PREVIEW.lock() = Some((script.to_string(), target, run));
+        });
+    });
+    let ui_weak = ui.as_weak();
+    ui.on_apply_script(move |script, target| {
+        let run = match PREVIEW.lock().take() {
+            Some((preview_script, preview_target, run)) if preview_script == script.as_str() && preview_target == target => run,
+            _ => {
+                ui_weak.unwrap().invoke_report("Preview the script first".into());
+                return;
+            }
+        };
+        run.write().process_or_report(ui_weak.clone(), |_| {
+            ui_weak.unwrap().invoke_set_script_preview(changes_model(&[]), "".into(), "Script results written".into());
+            let save = &run.output.save;
+            reload_if_current(save.game(), save.slot(), ui_weak.clone());
+            if !run.info_changes.is_empty() && FW_INFO.lock().game() == save.game() {
+                load_fnaf_world_info(save.game(), ui_weak.clone());
+            }
+            update_slot_summaries(ui_weak.clone());
+        });
+    });
+}
+
+fn update_journal(ui_weak: Weak<MainWindow>) {
+    journal::read().process_or_report(ui_weak.clone(), |entries| {
+        let cell = |s: String| slint::StandardListViewItem::from(slint::SharedString::from(s));
+        let value = |v: Option<u32>| v.map_or_else(|| "-".to_owned(), |v| v.to_string());
+        let rows = entries.iter().enumerate().map(|(i, e)| (i + 1, e)).rev().take(journal::SHOWN_ENTRIES).map(|(number, entry)| {
+            let row = match entry {
+                Some(e) => vec![
+                    cell(number.to_string()),
+                    cell(timestamp::format_seconds(e.time)),
+                    cell(e.source.to_string()),
+                    cell(e.target()),
+                    cell(e.key.clone()),
+                    cell(value(e.old)),
+                    cell(value(e.new)),
+                ],
+                None => vec![cell(number.to_string()), cell("unreadable".to_owned())],
+            };
+            slint::ModelRc::new(slint::VecModel::from(row))
+        }).collect::<Vec<_>>();
+        let ui = ui_weak.unwrap();
+        ui.set_journal_rows(slint::ModelRc::new(slint::VecModel::from(rows)));
+        ui.set_journal_count(entries.len() as i32);
+    });
+}
+
+fn register_callbacks_journal(ui: &MainWindow) {
+    let ui_weak = ui.as_weak();
+    ui.on_refresh_journal(move || update_journal(ui_weak.clone()));
+    let apply = |ui_weak: Weak<MainWindow>, revert: bool| move |from: i32, to: i32| {
+        let range = from.max(0) as usize..=to.max(0) as usize;
+        journal::apply(range, revert).process_or_report(ui_weak.clone(), |files| {
+            let done = if revert { "Reverted" } else { "Replayed" };
+            ui_weak.unwrap().set_journal_result(format!("{done} entries {from}-{to}, {files} files written").into());
+            let (game, slot) = current_slot();
+            reload_if_current(game, slot, ui_weak.clone());
+            let game = FW_INFO.lock().game();
+            load_fnaf_world_info(game, ui_weak.clone());
+            update_slot_summaries(ui_weak.clone());
+            update_journal(ui_weak.clone());
+        });
+    };
+    ui.on_replay_entries(apply(ui.as_weak(), false));
+    ui.on_revert_entries(apply(ui.as_weak(), true));
+}
+
+/// Starts or stops the API as configured and shows where it listens
+fn restart_api(shared: Arc<api::Shared>, ui_weak: Weak<MainWindow>) {
+    let config = config::CONFIG.lock().api.clone();
+    let status = match api::start(&config, shared) {
+        Ok(()) if config.enabled => format!("Listening on http://127.0.0.1:{}", config.port),
+        Ok(()) => "Disabled".to_owned(),
+        Err(e) => e.to_string(),
+    };
+    let ui = ui_weak.unwrap();
+    ui.set_api_enabled(config.enabled);
+    ui.set_api_port(config.port as i32);
+    ui.set_api_token(config.token.into());
+    ui.set_api_origins(config.allowed_origins.join(", ").into());
+    ui.set_api_status(status.into());
+}
+
+fn register_callbacks_api(ui: &MainWindow) {
+    changes::subscribe(api::publish);
+    // Weak handle can be sent to the server threads, but not shared between them
+    let ui_weak = Mutex::new(ui.as_weak());
+    let shared = Arc::new(api::Shared {
+        save: &SAVE1,
+        info: &FW_INFO,
+        changed: Box::new(move |game, slot| {
+            let _ = ui_weak.lock().upgrade_in_event_loop(move |ui| {
+                match slot {
+                    Some(slot) => reload_if_current(game, slot, ui.as_weak()),
+                    None if FW_INFO.lock().game() == game => load_fnaf_world_info(game, ui.as_weak()),
+                    None => {},
+                }
+                update_slot_summaries(ui.as_weak());
+            });
+        }),
+    });
+    restart_api(shared.clone(), ui.as_weak());
+    let ui_weak = ui.as_weak();
+    let edited = shared.clone();
+    ui.on_api_edited(move |enabled, port, origins| {
+        {
+            let mut config = config::CONFIG.lock();
+            config.api.enabled = enabled;
+            config.api.port = port.clamp(1024, u16::MAX as i32) as u16;
+            config.api.allowed_origins = api::parse_origins(&origins);
+            if config.api.token.is_empty() {
+                config.api.token = api::new_token();
+            }
+            config.save().report_to_user(ui_weak.clone());
+        }
+        restart_api(edited.clone(), ui_weak.clone());
+    });
+    let ui_weak = ui.as_weak();
+    ui.on_new_api_token(move || {
+        {
+            let mut config = config::CONFIG.lock();
+            config.api.token = api::new_token();
+            config.save().report_to_user(ui_weak.clone());
+        }
+        restart_api(shared.clone(), ui_weak.clone());
+    });
+}
+
+/// Writes the overlay and shows what was written
+fn update_overlay(ui_weak: Weak<MainWindow>) {
+    let preview = match overlay::refresh() {
+        Ok(Some(data)) => data,
+        Ok(None) => "Overlay is off".to_owned(),
+        Err(e) => format!("Can't write the overlay: {e:#}"),
+    };
+    ui_weak.unwrap().set_overlay_preview(preview.into());
+}
+
+fn register_callbacks_overlay(ui: &MainWindow) {
+    {
+        let config = &config::CONFIG.lock().overlay;
+        ui.set_overlay_enabled(config.enabled);
+        ui.set_overlay_path(config.path.display().to_string().into());
+        ui.set_overlay_format(overlay::OverlayFormat::ALL.iter().position(|f| *f == config.format).unwrap_or_default() as i32);
+        ui.set_overlay_template(config.template.as_str().into());
+    }
+    let (game, slot) = current_slot();
+    overlay::set_loaded(game, slot);
+    update_overlay(ui.as_weak());
+    changes::subscribe(|entries| {
+        if let Some(e) = entries.first() {
+            overlay::changed(e.game, e.slot);
+        }
+    });
+
+    let ui_weak = ui.as_weak();
+    ui.on_overlay_edited(move |enabled, path, format, template| {
+        {
+            let mut config = config::CONFIG.lock();
+            config.overlay = overlay::OverlayConfig {
+                enabled,
+                path: path.as_str().into(),
+                format: overlay::OverlayFormat::ALL.get(format as usize).copied().unwrap_or_default(),
+                template: template.to_string(),
+            };
+            config.save().report_to_user(ui_weak.clone());
+        }
+        let ui = ui_weak.unwrap();
+        ui.set_overlay_enabled(enabled);
+        ui.set_overlay_path(path);
+        ui.set_overlay_format(format);
+        ui.set_overlay_template(template);
+        update_overlay(ui_weak.clone());
+    });
+    let ui_weak = ui.as_weak();
+    ui.on_choose_overlay_path(move || {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("Text", &["txt"])
+            .add_filter("JSON", &["json"])
+            .add_filter("HTML", &["html"])
+            .save_file() else { return };
+        let ui = ui_weak.unwrap();
+        ui.invoke_overlay_edited(ui.get_overlay_enabled(), path.display().to_string().into(), ui.get_overlay_format(), ui.get_overlay_template());
+    });
+    // Progress the game itself writes
+    save_file_watcher::subscribe(|w| if !w.by_editor {
+        overlay::changed(w.game, w.slot);
+    }).report_to_user(ui.as_weak());
+}
+
+/// Decodes the code and places it into the target slot. Returns current target save and the decoded parts
+fn decode_by_options(code: &str, target: i32) -> anyhow::Result<(SaveData, SaveData, Option<InfoData>)> {
+    let Some((game, slot)) = slot_by_index(target) else { anyhow::bail!("Choose a save to load the code into") };
+    let (save, info) = share::decode(code)?.into_parts(game, slot)?;
+    Ok((SaveData::read(game, slot)?, save, info))
+}
+
+/// Slot the editor starts on, see [`settings::Session::start_slot`]
+fn start_slot() -> (Game, u8) {
+    let config = config::CONFIG.lock();
+    config.session.start_slot(&config.settings)
+}
+
+/// Tools by their names in the session
+const TOOLS: [(Tool, &str); 16] = [
+    (Tool::None, ""),
+    (Tool::Compare, "compare"),
+    (Tool::Merge, "merge"),
+    (Tool::Loadouts, "loadouts"),
+    (Tool::Randomizer, "randomizer"),
+    (Tool::Share, "share"),
+    (Tool::Bundle, "bundle"),
+    (Tool::Library, "library"),
+    (Tool::Practice, "practice"),
+    (Tool::Pins, "pins"),
+    (Tool::Discovery, "discovery"),
+    (Tool::Journal, "journal"),
+    (Tool::Api, "api"),
+    (Tool::Overlay, "overlay"),
+    (Tool::Script, "script"),
+    (Tool::Settings, "settings"),
+];
+
+/// Slot tabs by their names in the session
+const TABS: [(Screen, &str); 6] = [
+    (Screen::None, ""),
+    (Screen::Party, "party"),
+    (Screen::Chips, "chips"),
+    (Screen::Bytes, "bytes"),
+    (Screen::World, "world"),
+    (Screen::Progress, "progress"),
+];
+
+fn session_name<T: PartialEq>(names: &[(T, &str)], value: T) -> String {
+    names.iter().find(|(v, _)| *v == value).map_or("", |(_, name)| *name).to_owned()
+}
+
+/// Opens the slot, tab and tool of the last run and puts the window where it was
+fn restore_session(ui: &MainWindow) {
+    let (settings, session) = {
+        let config = config::CONFIG.lock();
+        (config.settings.clone(), config.session.clone())
+    };
+    if !settings.restore_session {
+        return;
+    }
+    let (game, slot) = session.start_slot(&settings);
+    ui.set_game(game);
+    if session.slot == 0 {
+        ui.set_slot(0);
+        load_fnaf_world_info(game, ui.as_weak());
+    } else {
+        ui.set_slot(slot as i32);
+        ui.set_selected_scr(TABS.iter().find(|(_, name)| *name == session.tab).map_or(Screen::None, |(tab, _)| *tab));
+    }
+    ui.set_tool(TOOLS.iter().find(|(_, name)| *name == session.tool).map_or(Tool::None, |(tool, _)| *tool));
+    if let Some(geometry) = session.window {
+        let window = ui.window();
+        window.set_position(slint::PhysicalPosition::new(geometry.x, geometry.y));
+        window.set_size(slint::PhysicalSize::new(geometry.width, geometry.height));
+        window.set_maximized(geometry.maximized);
+    }
+}
+
+/// Remembers where the editor is for the next start
+fn save_session(ui: &MainWindow) -> anyhow::Result<()> {
+    let window = ui.window();
+    let mut config = config::CONFIG.lock();
+    let maximized = window.is_maximized();
+    // Size of a maximized window is the screen, keep the one to go back to
+    let geometry = match config.session.window {
+        Some(prev) if maximized => settings::WindowGeometry { maximized, ..prev },
+        _ => {
+            let (position, size) = (window.position(), window.size());
+            settings::WindowGeometry { x: position.x, y: position.y, width: size.width, height: size.height, maximized }
+        },
+    };
+    config.session = settings::Session {
+        game: ui.get_game(),
+        slot: ui.get_slot() as u8,
+        tab: session_name(&TABS, ui.get_selected_scr()),
+        tool: session_name(&TOOLS, ui.get_tool()),
+        window: Some(geometry),
+    };
+    config.save()
+}
+
+fn register_callbacks_settings(ui: &MainWindow) {
+    {
+        let settings = &config::CONFIG.lock().settings;
+        ui.set_restore_session(settings.restore_session);
+        ui.set_saves_dir(settings.saves_dir.as_ref().map(|d| d.display().to_string()).unwrap_or_default().into());
+        ui.set_backup_policy(settings::BackupPolicy::ALL.iter().position(|p| *p == settings.backups).unwrap_or_default() as i32);
+        ui.set_keep_backups(settings.keep_backups as i32);
+        ui.set_color_scheme(settings::ColorScheme::ALL.iter().position(|s| *s == settings.color_scheme).unwrap_or_default() as i32);
+    }
+    ui.set_default_saves_dir(tokenizer::default_saves_dir().display().to_string().into());
+    let portable = if config::is_portable() { " (portable mode)" } else { "" };
+    ui.set_config_dir(format!("{}{portable}", config::config_dir().display()).into());
+
+    let ui_weak = ui.as_weak();
+    ui.on_settings_edited(move |restore_session, saves_dir, backups, keep_backups, color_scheme| {
+        {
+            let mut config = config::CONFIG.lock();
+            config.settings = settings::Settings {
+                restore_session,
+                saves_dir: (!saves_dir.is_empty()).then(|| saves_dir.as_str().into()),
+                backups: settings::BackupPolicy::ALL.get(backups as usize).copied().unwrap_or_default(),
+                keep_backups: keep_backups.max(0) as usize,
+                color_scheme: settings::ColorScheme::ALL.get(color_scheme as usize).copied().unwrap_or_default(),
+            };
+            config.save().report_to_user(ui_weak.clone());
+        }
+        let ui = ui_weak.unwrap();
+        ui.set_restore_session(restore_session);
+        ui.set_saves_dir(saves_dir);
+        ui.set_backup_policy(backups);
+        ui.set_keep_backups(keep_backups);
+        ui.set_color_scheme(color_scheme);
+    });
+    let ui_weak = ui.as_weak();
+    ui.on_choose_saves_dir(move || {
+        let Some(dir) = rfd::FileDialog::new().pick_folder() else { return };
+        let ui = ui_weak.unwrap();
+        ui.invoke_settings_edited(ui.get_restore_session(), dir.display().to_string().into(), ui.get_backup_policy(), ui.get_keep_backups(), ui.get_color_scheme());
+    });
+    let ui_weak = ui.as_weak();
+    ui.window().on_close_requested(move || {
+        if let Err(e) = save_session(&ui_weak.unwrap()) {
+            eprintln!("Can't save the session: {e:#}");
+        }
+        slint::CloseRequestResponse::HideWindow
+    });
+}
+
+fn register_callbacks_share(ui: &MainWindow) {
+    struct CodePreview {
+        code: String,
+        target: i32,
+        trophies: bool,
+        save: SaveData,
+        info: Option<InfoData>,
+    }
+    // Code is loaded only after it was previewed for the same slot
+    static PREVIEW: Mutex<Option<CodePreview>> = Mutex::new(None);
+
+    let ui_weak = ui.as_weak();
+    ui.on_make_code(move |source, trophies| {
+        let res = load_slot_or_file(source).and_then(|save| {
+            let Some(save) = save else { return Ok(None) };
+            let info = if trophies { Some(InfoData::read(save.game())?) } else { None };
+            share::encode(&save, info.as_ref()).map(Some)
+        });
+        res.process_or_report(ui_weak.clone(), |code| {
+            let Some(code) = code else { return };
+            let ui = ui_weak.unwrap();
+            ui.invoke_set_code_preview(changes_model(&[]), format!("Code is {} characters long", code.len()).into());
+            ui.set_share_code(code.into());
+        });
+    });
+    let ui_weak = ui.as_weak();
+    ui.on_preview_code(move |code, target, trophies| {
+        decode_by_options(&code, target).process_or_report(ui_weak.clone(), |(current, save, info)| {
+            let changes = diff::diff_saves(&current, &save);
+            let trophies_note = match (&info, trophies) {
+                (Some(_), true) => ". Trophies will be replaced",
+                (Some(_), false) => ". Code has trophies, check \"Replace trophies\" to load them",
+                (None, _) => "",
+            };
+            let summary = format!("{} changes{trophies_note}. Press \"Load\" to write them", changes.len());
+            ui_weak.unwrap().invoke_set_code_preview(changes_model(&changes), summary.into());
+            *PREVIEW.lock() = Some(CodePreview { code: code.to_string(), target, trophies, save, info: info.filter(|_| trophies) });
+        });
+    });
+    let ui_weak = ui.as_weak();
+    ui.on_apply_code(move |code, target, trophies| {
+        let (save, info) = match PREVIEW.lock().take() {
+            Some(p) if p.code == code.as_str() && p.target == target && p.trophies == trophies => (p.save, p.info),
+            _ => {
+                ui_weak.unwrap().invoke_report("Preview the code first".into());
+                return;
+            }
+        };
+        let res = save.save(Source::Gui).and_then(|_| info.as_ref().map(|i| i.save(Source::Gui)).transpose());
+        res.process_or_report(ui_weak.clone(), |_| {
+            ui_weak.unwrap().invoke_set_code_preview(changes_model(&[]), "Loaded".into());
+            // Trophies shown now are written back on the next edit, so they must be the new ones
+            let mut current_info = FW_INFO.lock();
+            if let Some(info) = info.filter(|i| i.game() == current_info.game()) {
+                *current_info = info;
+            }
+            drop(current_info);
+            reload_if_current(save.game(), save.slot(), ui_weak.clone());
+            update_slot_summaries(ui_weak.clone());
+        });
+    });
+}
+
+fn register_callbacks_bundle(ui: &MainWindow) {
+    // Archive opened for restoring, in the same order as entries in ui
+    static OPENED: Mutex<Vec<bundle::Incoming>> = Mutex::new(Vec::new());
+
+    let ui_weak = ui.as_weak();
+    ui.on_export_bundle(move || {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("Zip archive", &["zip"])
+            .set_file_name(format!("fnaf_saves_{}.zip", timestamp::file_stamp(timestamp::now())))
+            .save_file()
+        else { return };
+        bundle::export(&path).process_or_report(ui_weak.clone(), |manifest| {
+            ui_weak.unwrap().set_bundle_summary(format!("{} files packed into {}", manifest.files.len(), path.display()).into());
+        });
+    });
+    let ui_weak = ui.as_weak();
+    ui.on_open_bundle(move || {
+        let Some(path) = rfd::FileDialog::new().add_filter("Zip archive", &["zip"]).pick_file() else { return };
+        bundle::open(&path).process_or_report(ui_weak.clone(), |(manifest, files)| {
+            let entries = files.iter().map(|f| BundleEntry {
+                title: f.file.title().into(),
+                status: f.status.to_string().into(),
+                resolution: bundle::Resolution::ALL.iter().position(|r| *r == f.default_resolution()).unwrap_or_default() as i32,
+            }).collect::<Vec<_>>();
+            let summary = format!(
+                "Packed by editor {} at {} UTC. Choose what to do with each file and press \"Restore\"",
+                manifest.editor_version,
+                timestamp::format(manifest.created),
+            );
+            let ui = ui_weak.unwrap();
+            ui.set_bundle_entries(std::rc::Rc::new(VecModel::from(entries)).into());
+            ui.set_bundle_summary(summary.into());
+            *OPENED.lock() = files;
+        });
+    });
+    let ui_weak = ui.as_weak();
+    ui.on_import_bundle(move |entries| {
+        let files = std::mem::take(&mut *OPENED.lock());
+        let res = files.iter().zip(entries.iter()).try_fold(0, |restored, (file, entry)| {
+            let resolution = bundle::Resolution::ALL.get(entry.resolution as usize).copied().unwrap_or(bundle::Resolution::Skip);
+            Ok::<_, anyhow::Error>(restored + file.restore(resolution, Source::Gui)? as usize)
+        });
+        let ui = ui_weak.unwrap();
+        ui.set_bundle_entries(std::rc::Rc::new(VecModel::from(Vec::<BundleEntry>::new())).into());
+        res.process_or_report(ui_weak.clone(), |restored| {
+            ui.set_bundle_summary(format!("{restored} files restored").into());
+            let (game, slot) = {
+                let save = SAVE1.lock();
+                (save.game(), save.slot())
+            };
+            load_fnaf_world_info(game, ui_weak.clone());
+            reload_if_current(game, slot, ui_weak.clone());
+            update_slot_summaries(ui_weak.clone());
+        });
+    });
+}
+
+/// Search text of the library screen
+static LIBRARY_QUERY: Mutex<String> = Mutex::new(String::new());
+
+/// Sends library entries matching current search to the ui
+fn update_library(ui_weak: Weak<MainWindow>) {
+    let library = library::Library::default();
+    let query = LIBRARY_QUERY.lock().clone();
+    library.list().process_or_report(ui_weak.clone(), |entries| {
+        let items = entries.iter().filter(|e| e.matches(&query)).map(|e| LibraryItem {
+            id: e.id.as_str().into(),
+            name: e.name.as_str().into(),
+            game: export::game_title(e.game).into(),
+            tags: e.tags.join(", ").into(),
+            notes: e.notes.as_str().into(),
+            created: timestamp::format(e.created).into(),
+            summary: match library.load(e) {
+                Ok(save) => progress::Progress::new(&save, None).summary(),
+                Err(_) => "Unreadable save".to_owned(),
+            }.into(),
+        }).collect::<Vec<_>>();
+        ui_weak.unwrap().set_library_items(std::rc::Rc::new(VecModel::from(items)).into());
+    });
+}
+
+/// Library entry by its id
+fn library_entry(id: &str) -> anyhow::Result<library::LibraryEntry> {
+    library::Library::default().list()?.into_iter().find(|e| e.id == id).ok_or_else(|| anyhow::anyhow!("Save is not in the library anymore"))
+}
+
+fn register_callbacks_library(ui: &MainWindow) {
+    update_library(ui.as_weak());
+    let ui_weak = ui.as_weak();
+    ui.on_store_save(move |source, name, tags| {
+        let Some((game, slot)) = slot_by_index(source) else { return };
+        let res = library::Library::default().store_slot(&name, "", library::parse_tags(&tags), game, slot);
+        res.process_or_report(ui_weak.clone(), |entry| {
+            ui_weak.unwrap().set_library_result(format!("Stored as \"{}\"", entry.name).into());
+            update_library(ui_weak.clone());
+        });
+    });
+    let ui_weak = ui.as_weak();
+    ui.on_filter_library(move |query| {
+        *LIBRARY_QUERY.lock() = query.to_string();
+        update_library(ui_weak.clone());
+    });
+    let ui_weak = ui.as_weak();
+    ui.on_update_save(move |id, name, tags, notes| {
+        let res = library_entry(&id).and_then(|entry| {
+            let entry = library::LibraryEntry { name: name.trim().to_owned(), tags: library::parse_tags(&tags), notes: notes.to_string(), ..entry };
+            library::Library::default().update(&entry)
+        });
+        res.process_or_report(ui_weak.clone(), |_| {
+            ui_weak.unwrap().set_library_result("Saved".into());
+            update_library(ui_weak.clone());
+        });
+    });
+    let ui_weak = ui.as_weak();
+    ui.on_delete_save(move |id| {
+        let res = library_entry(&id).and_then(|entry| library::Library::default().remove(&entry));
+        res.process_or_report(ui_weak.clone(), |_| update_library(ui_weak.clone()));
+    });
+    let ui_weak = ui.as_weak();
+    ui.on_activate_save(move |id, target| {
+        let Some((game, slot)) = slot_by_index(target) else { return };
+        let res = library_entry(&id).and_then(|entry| Ok((library::Library::default().activate(&entry, game, slot, Source::Gui)?, entry)));
+        res.process_or_report(ui_weak.clone(), |(backup, entry)| {
+            let backup = backup.map(|p| format!(". Replaced save is backed up as {}", p.display())).unwrap_or_default();
+            ui_weak.unwrap().set_library_result(format!("\"{}\" is now in slot {slot}{backup}", entry.name).into());
+            reload_if_current(game, slot, ui_weak.clone());
+            update_slot_summaries(ui_weak.clone());
+        });
+    });
+}
+
+/// Game and slot (starting from 1) of the save being edited
+fn current_slot() -> (Game, u8) {
+    let save = SAVE1.lock();
+    (save.game(), save.slot())
+}
+
+/// Shows name of the save being edited on the screens of per slot tools
+fn update_current_slot_name(ui: &MainWindow) {
+    let (game, slot) = current_slot();
+    let index = match game {
+        Game::WorldVanilla => slot as usize - 1,
+        Game::WorldRefreshed => slot as usize + 2,
+    };
+    let name = ui.global::<SlotNames>().get_saves().row_data(index).unwrap_or_default();
+    ui.set_current_slot_name(name);
+}
+
+/// Sends practice states of the current slot to the ui
+fn update_practice_states(ui_weak: Weak<MainWindow>) {
+    let (game, slot) = current_slot();
+    quickstate::list(game, slot).process_or_report(ui_weak.clone(), |states| {
+        let states = states.into_iter()
+            .map(|t| t.map(timestamp::format).unwrap_or_default().into())
+            .collect::<Vec<slint::SharedString>>();
+        let ui = ui_weak.unwrap();
+        update_current_slot_name(&ui);
+        ui.set_practice_states(std::rc::Rc::new(VecModel::from(states)).into());
+    });
+}
+
+fn register_callbacks_practice(ui: &MainWindow) {
+    let ui_weak = ui.as_weak();
+    ui.on_practice_requested(move || update_practice_states(ui_weak.clone()));
+    let ui_weak = ui.as_weak();
+    ui.on_capture_state(move |number| {
+        let (game, slot) = current_slot();
+        quickstate::capture(game, slot, number as u8).process_or_report(ui_weak.clone(), |_| {
+            ui_weak.unwrap().invoke_report(format!("State {number} captured").into());
+            update_practice_states(ui_weak.clone());
+        });
+    });
+    let ui_weak = ui.as_weak();
+    ui.on_restore_state(move |number| {
+        let (game, slot) = current_slot();
+        quickstate::restore(game, slot, number as u8, Source::Gui).process_or_report(ui_weak.clone(), |_| {
+            ui_weak.unwrap().invoke_report(format!("State {number} restored").into());
+            load_fnaf_world_save(game, slot, ui_weak.clone());
+            update_slot_summaries(ui_weak.clone());
+        });
+    });
+    let ui_weak = ui.as_weak();
+    ui.on_delete_state(move |number| {
+        let (game, slot) = current_slot();
+        quickstate::delete(game, slot, number as u8).process_or_report(ui_weak.clone(), |_| update_practice_states(ui_weak.clone()));
+    });
+}
+
+fn register_callbacks_game_process(ui: &MainWindow) {
+    ui.set_defer_writes(config::CONFIG.lock().defer_writes);
+    let ui_weak = ui.as_weak();
+    ui.on_defer_writes_edited(move |value| {
+        let mut config = config::CONFIG.lock();
+        config.defer_writes = value;
+        config.save().report_to_user(ui_weak.clone());
+    });
+    let ui_weak = ui.as_weak();
+    game_process::start_watching(move |running, flushed| {
+        let pending = game_process::pending() as i32;
+        let _ = ui_weak.upgrade_in_event_loop(move |ui| {
+            ui.set_game_running(running);
+            ui.set_pending_writes(pending);
+            let Some(flushed) = flushed else { return };
+            let mut report = Vec::new();
+            if flushed.written > 0 {
+                report.push(format!("Game closed, {} held edits written", flushed.written));
+            }
+            for (path, e) in &flushed.failed {
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                report.push(format!("Can't write held edits of {name}, they stay held: {e:#}"));
+            }
+            if !report.is_empty() {
+                ui.invoke_report(report.join("\n").into());
+            }
+        });
+    });
+}
+
+/// Sends pins of the current slot and the log to the ui
+fn update_pins(ui_weak: Weak<MainWindow>) {
+    let (game, slot) = current_slot();
+    let pins = {
+        let config = config::CONFIG.lock();
+        pins::PinField::ALL.iter().map(|field| {
+            let pin = pins::find(&config.pins, game, slot, *field);
+            PinInfo {
+                name: field.name().into(),
+                value: pin.map_or(0, |p| p.value) as i32,
+                enabled: pin.is_some_and(|p| p.enabled),
+            }
+        }).collect::<Vec<_>>()
+    };
+    let log = pins::read_log().into_iter()
+        .map(|l| slint::StandardListViewItem::from(slint::SharedString::from(l)))
+        .collect::<Vec<_>>();
+    let ui = ui_weak.unwrap();
+    update_current_slot_name(&ui);
+    ui.set_pins(std::rc::Rc::new(VecModel::from(pins)).into());
+    ui.set_pins_log(std::rc::Rc::new(VecModel::from(log)).into());
+}
+
+/// Puts the slot back into ui after pins were re-applied to it
+fn pins_reapplied(game: Game, slot: u8, line: String, ui_weak: Weak<MainWindow>) {
+    ui_weak.unwrap().invoke_report(line.into());
+    reload_if_current(game, slot, ui_weak.clone());
+    update_slot_summaries(ui_weak.clone());
+    update_pins(ui_weak);
+}
+
+fn register_callbacks_pins(ui: &MainWindow) {
+    let ui_weak = ui.as_weak();
+    ui.on_pins_requested(move || update_pins(ui_weak.clone()));
+    let ui_weak = ui.as_weak();
+    ui.on_pin_edited(move |index, enabled, value| {
+        let Some(field) = pins::PinField::ALL.get(index as usize) else { return };
+        let (game, slot) = current_slot();
+        let res = {
+            let mut config = config::CONFIG.lock();
+            pins::set(&mut config.pins, game, slot, pins::Pin { field: *field, value: value.max(0) as u32, enabled });
+            config.save()
+        };
+        res.and_then(|_| pins::enforce(game, slot)).process_or_report(ui_weak.clone(), |line| match line {
+            Some(line) => pins_reapplied(game, slot, line, ui_weak.clone()),
+            None => update_pins(ui_weak.clone()),
+        });
+    });
+    let ui_weak = ui.as_weak();
+    pins::start_watching(move |game, slot, res| {
+        let _ = ui_weak.upgrade_in_event_loop(move |ui| match res {
+            Ok(line) => pins_reapplied(game, slot, line, ui.as_weak()),
+            Err(e) => ui.invoke_report(format!("Can't re-apply pins: {e}").into()),
+        });
+    }).report_to_user(ui.as_weak());
+}
+
+static RECORDING: Mutex<Option<discovery::Recording>> = Mutex::new(None);
+/// Changes and notes of the last recording in order they were made
+static RECORDING_ENTRIES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+fn update_discovery(ui_weak: Weak<MainWindow>) {
+    let ui = ui_weak.unwrap();
+    let recording = RECORDING.lock();
+    ui.set_recording(recording.is_some());
+    if let Some(recording) = recording.as_ref() {
+        ui.set_recording_log(recording.log_path().display().to_string().into());
+    }
+    else {
+        update_current_slot_name(&ui);
+    }
+    let entries = RECORDING_ENTRIES.lock().iter().rev()
+        .map(|e| slint::StandardListViewItem::from(slint::SharedString::from(e.as_str())))
+        .collect::<Vec<_>>();
+    ui.set_recording_entries(std::rc::Rc::new(VecModel::from(entries)).into());
+}
+
+fn register_callbacks_discovery(ui: &MainWindow) {
+    let ui_weak = ui.as_weak();
+    ui.on_discovery_requested(move || update_discovery(ui_weak.clone()));
+    let ui_weak = ui.as_weak();
+    ui.on_start_recording(move || {
+        let (game, slot) = current_slot();
+        discovery::Recording::start(game, slot).process_or_report(ui_weak.clone(), |recording| {
+            *RECORDING_ENTRIES.lock() = vec![format!("{} recording started", timestamp::format_seconds(timestamp::now()))];
+            *RECORDING.lock() = Some(recording);
+            update_discovery(ui_weak.clone());
+        });
+    });
+    let ui_weak = ui.as_weak();
+    ui.on_stop_recording(move || {
+        if RECORDING.lock().take().is_some() {
+            RECORDING_ENTRIES.lock().push(format!("{} recording stopped", timestamp::format_seconds(timestamp::now())));
+        }
+        update_discovery(ui_weak.clone());
+    });
+    let ui_weak = ui.as_weak();
+    ui.on_add_note(move |note| {
+        let Some(res) = RECORDING.lock().as_ref().map(|r| r.annotate(&note)) else { return };
+        res.process_or_report(ui_weak.clone(), |line| {
+            RECORDING_ENTRIES.lock().push(line);
+            update_discovery(ui_weak.clone());
+        });
+    });
+    let ui_weak = ui.as_weak();
+    save_file_watcher::subscribe(move |w| {
+        let Some(slot) = w.slot else { return };
+        let res = match RECORDING.lock().as_mut() {
+            // Only the game's changes are logged
+            Some(recording) if recording.is_of(w.game, slot) && w.by_editor => recording.rebase().map(|_| Vec::new()),
+            Some(recording) if recording.is_of(w.game, slot) => recording.snapshot(),
+            _ => return,
+        };
+        let time = timestamp::format_seconds(timestamp::now());
+        let _ = ui_weak.upgrade_in_event_loop(move |ui| match res {
+            Ok(changes) => {
+                RECORDING_ENTRIES.lock().extend(changes.iter().map(|c| format!("{time}  {c}")));
+                update_discovery(ui.as_weak());
+            },
+            Err(e) => ui.invoke_report(format!("Can't record the save: {e}").into()),
+        });
+    }).report_to_user(ui.as_weak());
+}
+
+/// Files which another program changed, the first one is shown
+static CONFLICTS: Mutex<Vec<std::path::PathBuf>> = Mutex::new(Vec::new());
+
+/// Whether the conflicting file is the edited save (`Some(false)`) or trophies (`Some(true)`)
+fn conflict_target(path: &std::path::Path) -> Option<bool> {
+    let save = SAVE1.lock();
+    if tokenizer::construct_path(save.game(), save.slot() - 1).is_ok_and(|p| p == path) {
+        return Some(false);
+    }
+    (tokenizer::info_path(FW_INFO.lock().game()) == path).then_some(true)
+}
+
+/// Shows the next conflict, if there is one
+fn show_conflict(ui: &MainWindow) {
+    let path = CONFLICTS.lock().first().map(|p| p.display().to_string()).unwrap_or_default();
+    ui.set_conflict_file(path.into());
+}
+
+/// Takes the shown conflict
+fn take_conflict() -> Option<std::path::PathBuf> {
+    let mut conflicts = CONFLICTS.lock();
+    (!conflicts.is_empty()).then(|| conflicts.remove(0))
+}
+
+/// Reads the conflicting file again into the editor if it's loaded
+fn reload_conflict_target(path: &std::path::Path, ui_weak: Weak<MainWindow>) {
+    match conflict_target(path) {
+        Some(false) => {
+            let (game, slot) = current_slot();
+            load_fnaf_world_save(game, slot, ui_weak.clone());
+        },
+        Some(true) => {
+            let game = FW_INFO.lock().game();
+            load_fnaf_world_info(game, ui_weak.clone());
+        },
+        None => {},
+    }
+    update_slot_summaries(ui_weak);
+}
+
+fn register_callbacks_file_guard(ui: &MainWindow) {
+    let ui_weak = ui.as_weak();
+    file_guard::on_conflict(move |path| {
+        {
+            let mut conflicts = CONFLICTS.lock();
+            if !conflicts.contains(&path) {
+                conflicts.push(path);
+            }
+        }
+        let _ = ui_weak.upgrade_in_event_loop(|ui| show_conflict(&ui));
+    });
+    let ui_weak = ui.as_weak();
+    ui.on_conflict_reload(move || {
+        let Some(path) = take_conflict() else { return };
+        // Edits held for the game are dropped in favor of what it saved
+        file_guard::take_refused(&path);
+        reload_conflict_target(&path, ui_weak.clone());
+        show_conflict(&ui_weak.unwrap());
+    });
+    let ui_weak = ui.as_weak();
+    ui.on_conflict_overwrite(move || {
+        let Some(path) = take_conflict() else { return };
+        let refused = file_guard::take_refused(&path);
+        let res = (|| {
+            let target = conflict_target(&path);
+            if refused.is_none() && target.is_none() {
+                anyhow::bail!("Nothing to write, repeat the action to overwrite the file");
+            }
+            let backup = if path.exists() { Some(config::backup(&path)?) } else { None };
+            match (refused, target) {
+                (Some(data), _) => file_guard::write_bytes(path.clone(), data, &Default::default())?,
+                (None, Some(true)) => {
+                    let info = FW_INFO.lock();
+                    info.ignore_changes_on_disk();
+                    info.save(Source::Gui)?;
+                },
+                (None, _) => {
+                    let save = SAVE1.lock();
+                    save.ignore_changes_on_disk();
+                    save.save(Source::Gui)?;
+                },
+            }
+            Ok(backup)
+        })();
+        res.process_or_report(ui_weak.clone(), |backup| {
+            let backup = backup.map(|p| format!(". Other version is backed up as {}", p.display())).unwrap_or_default();
+            ui_weak.unwrap().invoke_report(format!("Overwritten{backup}").into());
+            reload_conflict_target(&path, ui_weak.clone());
+        });
+        show_conflict(&ui_weak.unwrap());
+    });
+    let ui_weak = ui.as_weak();
+    ui.on_conflict_cancel(move || {
+        if let Some(path) = take_conflict() {
+            file_guard::take_refused(&path);
+        }
+        show_conflict(&ui_weak.unwrap());
+    });
+}
+
+/// Sends loadouts from config to the ui
+fn update_loadouts(ui_weak: Weak<MainWindow>) {
+    let config = config::CONFIG.lock();
+    let team = |kind: catalog::Kind, ids: &[u32]| {
+        let names = ids.iter().filter(|id| **id != 0).map(|id| catalog::name(kind, *id)).collect::<Vec<_>>();
+        if names.is_empty() { "-".to_owned() } else { names.join(", ") }
+    };
+    let infos = config.loadouts.iter().map(|l| LoadoutInfo {
+        name: l.name.as_str().into(),
+        team: format!(
+            "Party: {}\nChips: {}\nBytes: {}",
+            team(catalog::Kind::Character, &l.party),
+            team(catalog::Kind::Chip, &l.chips),
+            team(catalog::Kind::Byte, &l.bytes),
+        ).into(),
+    }).collect::<Vec<_>>();
+    let items = config.loadouts.iter().map(|l| slint::StandardListViewItem::from(slint::SharedString::from(l.name.as_str()))).collect::<Vec<_>>();
+    ui_weak.unwrap().invoke_set_loadouts(
+        std::rc::Rc::new(VecModel::from(infos)).into(),
+        std::rc::Rc::new(VecModel::from(items)).into(),
+    );
+}
+
+fn register_callbacks_loadouts(ui: &MainWindow) {
+    update_loadouts(ui.as_weak());
+    let ui_weak = ui.as_weak();
+    ui.on_save_loadout(move |name| {
+        let loadout = loadout::Loadout::from_save(&name, &SAVE1.lock());
+        let mut config = config::CONFIG.lock();
+        // Same name replaces the old team
+        match config.loadouts.iter_mut().find(|l| l.name == loadout.name) {
+            Some(old) => *old = loadout,
+            None => config.loadouts.push(loadout),
+        }
+        let res = config.save();
+        drop(config);
+        res.process_or_report(ui_weak.clone(), |_| update_loadouts(ui_weak.clone()));
+    });
+    let ui_weak = ui.as_weak();
+    ui.on_delete_loadout(move |idx| {
+        let mut config = config::CONFIG.lock();
+        if (idx as usize) < config.loadouts.len() {
+            config.loadouts.remove(idx as usize);
+        }
+        let res = config.save();
+        drop(config);
+        res.process_or_report(ui_weak.clone(), |_| update_loadouts(ui_weak.clone()));
+    });
+    let ui_weak = ui.as_weak();
+    ui.on_apply_loadout(move |idx, target, unlock| {
+        let Some(loadout) = config::CONFIG.lock().loadouts.get(idx as usize).cloned() else { return };
+        let Some((game, slot)) = slot_by_index(target) else { return };
+        let res = SaveData::read(game, slot).and_then(|mut save| {
+            let report = loadout.apply(&mut save, unlock);
+            save.save(Source::Gui)?;
+            Ok(report)
+        });
+        res.process_or_report(ui_weak.clone(), |report| {
+            ui_weak.unwrap().set_loadout_result(report.summary().into());
+            reload_if_current(game, slot, ui_weak.clone());
+            update_slot_summaries(ui_weak.clone());
+        });
+    });
+}
+
+/// Short progress summaries shown under slot buttons, by position in slot lists
+struct SlotSummaries {
+    /// Increased every time a summary is forgotten, so one calculated from an older file isn't kept
+    generation: u64,
+    /// None until calculated or after the slot or its trophies changed
+    summaries: [Option<String>; 7],
+}
+
+static SLOT_SUMMARIES: Mutex<SlotSummaries> = Mutex::new(SlotSummaries { generation: 0, summaries: [const { None }; 7] });
+
+/// Forgets summaries of the slot (starting from 1) or of every slot of the game if trophies (none) changed
+fn forget_slot_summaries(game: Game, slot: Option<u8>) {
+    let mut cache = SLOT_SUMMARIES.lock();
+    cache.generation += 1;
+    for (idx, summary) in cache.summaries.iter_mut().enumerate() {
+        if slot_by_index(idx as i32).is_some_and(|(g, s)| g == game && slot.is_none_or(|slot| slot == s)) {
+            *summary = None;
+        }
+    }
+}
+
+/// Forgets summaries of files written by the editor (including ones held for the running game) or on disk,
+/// by the game or when held writes are flushed
+fn watch_slot_summaries(ui: &MainWindow) {
+    changes::subscribe(|entries| {
+        if let Some(e) = entries.first() {
+            forget_slot_summaries(e.game, e.slot);
+        }
+    });
+    let ui_weak = ui.as_weak();
+    save_file_watcher::subscribe(move |w| {
+        forget_slot_summaries(w.game, w.slot);
+        update_slot_summaries(ui_weak.clone());
+    }).report_to_user(ui.as_weak());
+}
+
+fn slot_summary(game: Game, slot: u8) -> String {
+    let exists = tokenizer::construct_path(game, slot - 1).is_ok_and(|p| p.exists());
+    match SaveData::read(game, slot) {
+        Ok(save) if exists => {
+            let info = InfoData::read(game).ok();
+            progress::Progress::new(&save, info.as_ref()).summary()
+        },
+        Ok(_) => "Empty".to_owned(),
+        Err(_) => "Unreadable save".to_owned(),
+    }
+}
+
+/// Shows short progress summaries under slot buttons. Only slots which changed since the last time are read
+fn update_slot_summaries(ui_weak: Weak<MainWindow>) {
+    std::thread::spawn(move || {
+        let (generation, cached) = {
+            let cache = SLOT_SUMMARIES.lock();
+            (cache.generation, cache.summaries.clone())
+        };
+        let summaries = (0..7).map_while(slot_by_index).zip(cached)
+            .map(|((game, slot), cached)| cached.unwrap_or_else(|| slot_summary(game, slot)))
+            .collect::<Vec<_>>();
+        let mut cache = SLOT_SUMMARIES.lock();
+        if cache.generation == generation {
+            for (cached, summary) in cache.summaries.iter_mut().zip(&summaries) {
+                *cached = Some(summary.clone());
+            }
+        }
+        drop(cache);
+        let _ = ui_weak.upgrade_in_event_loop(move |ui| {
+            let summaries = summaries.into_iter().map(slint::SharedString::from).collect::<Vec<_>>();
+            ui.set_slot_summaries(std::rc::Rc::new(VecModel::from(summaries)).into());
+        });
     });
 }
 
 /// Loads specified save into global and updates ui
 fn load_fnaf_world_save(game: Game, slot: u8, ui_weak: Weak<MainWindow>) {
-    println!("Loading save {slot}");
     let data = SaveData::read(game, slot).unwrap_or_default();
     let mut save = SAVE1.lock();
     *save = data;
+    drop(save);
+    overlay::set_loaded(game, slot);
     init_fnaf_world_save_data(ui_weak);
 }
 
@@ -340,7 +1698,8 @@ fn init_fnaf_world_save_data(ui_weak: Weak<MainWindow>) {
                 save.save_pos.1 as i32,
                 save.tokens as i32,
                 save.pearls as i32,
-                save.armor_id as i32,
+                std::rc::Rc::new(VecModel::from(armor::PIECES.iter().map(|p| save.armor_owned.contains(&p.id)).collect::<Vec<_>>())).into(),
+                save.armor as i32,
                 std::rc::Rc::new(VecModel::from_slice(save.jumps.as_slice())).into(),
                 save.porkpatch_button,
                 std::rc::Rc::new(VecModel::from_slice(save.guardians.as_slice())).into(),
@@ -349,6 +1708,7 @@ fn init_fnaf_world_save_data(ui_weak: Weak<MainWindow>) {
                 save.flags.contains(&tokenizer::Token::Portal),
                 save.flags.contains(&tokenizer::Token::Lantern),
             );
+            ui.set_armor_consistent(armor::is_consistent(&save.armor_owned, save.armor));
             Timer::single_shot(Duration::from_millis(200), || REJECT_SAVE.store(false, std::sync::atomic::Ordering::Relaxed));
         }).unwrap()
     });
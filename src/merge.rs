@@ -0,0 +1,111 @@
+//! Combining progress of two saves into one
+
+use clap::ValueEnum;
+use std::collections::HashSet;
+use crate::tokenizer::{SaveData, Token};
+
+/// How to pick a value which can't be simply united
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ScalarRule {
+    #[default]
+    First,
+    Second,
+    Max,
+    Min,
+}
+
+impl ScalarRule {
+    pub const ALL: [Self; 4] = [Self::First, Self::Second, Self::Max, Self::Min];
+
+    pub fn apply<T: Ord + Copy>(self, first: T, second: T) -> T {
+        match self {
+            Self::First => first,
+            Self::Second => second,
+            Self::Max => first.max(second),
+            Self::Min => first.min(second),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MergeRules {
+    pub tokens: ScalarRule,
+    pub pearls: ScalarRule,
+    pub time: ScalarRule,
+    pub save_pos: ScalarRule,
+}
+
+/// Unites progress of both saves. Everything not covered by progress or `rules` (party, gamemode, difficulty, ...) is taken from `first`.
+/// Result is placed into the same game and slot as `first`
+pub fn merge(first: &SaveData, second: &SaveData, rules: &MergeRules) -> SaveData {
+    let mut res = first.clone();
+
+    for (id, c) in second.characters.iter() {
+        let entry = res.characters.entry(*id).or_insert(*c);
+        // Exp to next belongs to the level, so take them together
+        if c.lvl > entry.lvl {
+            *entry = *c;
+        }
+    }
+    res.chips.extend(second.chips.iter().copied());
+    res.bytes.extend(second.bytes.iter().copied());
+    // Entries not covered by progress (unknown or out of range ones), keep one value of every key like other values
+    let first_keys = first.flags.iter().filter_map(flag_key).collect::<HashSet<_>>();
+    res.flags.extend(second.flags.iter().filter(|t| flag_key(t).is_none_or(|key| !first_keys.contains(&key))).cloned());
+    for (r, s) in res.jumps.iter_mut().zip(second.jumps) {
+        *r |= s;
+    }
+    for (r, s) in res.guardians.iter_mut().zip(second.guardians) {
+        *r |= s;
+    }
+    for (r, s) in res.clocks_found.iter_mut().zip(second.clocks_found) {
+        *r |= s;
+    }
+    res.porkpatch_button |= second.porkpatch_button;
+    res.armor = res.armor.max(second.armor);
+    res.armor_owned.extend(second.armor_owned.iter().copied());
+
+    res.tokens = rules.tokens.apply(first.tokens, second.tokens);
+    res.pearls = rules.pearls.apply(first.pearls, second.pearls);
+    res.time = rules.time.apply(first.time, second.time);
+    res.save_pos = rules.save_pos.apply(first.save_pos, second.save_pos);
+    res
+}
+
+/// Key of an entry as written in the save
+fn flag_key(token: &Token) -> Option<String> {
+    token.to_string().split_once('=').map(|(key, _)| key.to_owned())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tokenizer::test_save;
+
+    #[test]
+    fn unites_progress() {
+        let first = test_save("[fnafw]\n1have=1\n1lv=10\n1next=500\n2have=1\n2lv=3\n2next=70\nc1=1\ntokens=100\nhour=2");
+        let second = test_save("[fnafw]\n1have=1\n1lv=4\n1next=90\n2have=1\n2lv=8\n2next=300\n5have=1\nc4=1\nsw1=1\ntokens=50\nhour=5");
+        let rules = MergeRules { tokens: ScalarRule::Max, time: ScalarRule::Second, ..Default::default() };
+        let res = merge(&first, &second, &rules);
+
+        assert_eq!((res.characters[&1].lvl, res.characters[&1].next), (10, 500));
+        assert_eq!((res.characters[&2].lvl, res.characters[&2].next), (8, 300));
+        assert!(res.characters.contains_key(&5));
+        assert!(res.chips.contains(&1) && res.chips.contains(&4));
+        assert!(res.jumps[1]);
+        assert_eq!(res.tokens, 100);
+        assert_eq!(res.time.0, 5);
+    }
+
+    #[test]
+    fn keeps_one_value_of_unknown_keys() {
+        let first = test_save("[fnafw]\nmystery=1\nonlyfirst=3\ns9=60");
+        let second = test_save("[fnafw]\nmystery=2\nonlysecond=4\ns9=61");
+        let res = merge(&first, &second, &MergeRules::default());
+
+        let mut raw = res.flags.iter().map(Token::to_string).collect::<Vec<_>>();
+        raw.sort();
+        assert_eq!(raw, ["mystery=1", "onlyfirst=3", "onlysecond=4", "s9=60"]);
+    }
+}
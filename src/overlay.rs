@@ -0,0 +1,162 @@
+//! Live progress of the loaded save for stream overlays: a file rewritten every time the save or trophies change.
+//! Template placeholders are names of progress categories (`{characters}` gives `32/48`, `{characters_done}`, `{characters_total}`),
+//! `{percent}`, `{game}` and `{slot}`
+
+use std::path::{Path, PathBuf};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map};
+use crate::progress::Progress;
+use crate::tokenizer::{InfoData, SaveData};
+use crate::Game as FnafWorldGame;
+
+pub const DEFAULT_TEMPLATE: &str = "characters {characters}, chips {chips}, endings {endings}";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverlayFormat {
+    /// Template as is
+    #[default]
+    Text,
+    /// Every count and the template as `text`
+    Json,
+    /// Template inside a page which reloads itself, for browser sources
+    Html,
+}
+
+impl OverlayFormat {
+    /// In the order of the ui
+    pub const ALL: [Self; 3] = [Self::Text, Self::Json, Self::Html];
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OverlayConfig {
+    pub enabled: bool,
+    pub path: PathBuf,
+    pub format: OverlayFormat,
+    pub template: String,
+}
+
+impl Default for OverlayConfig {
+    fn default() -> Self {
+        Self { enabled: false, path: PathBuf::new(), format: OverlayFormat::Text, template: DEFAULT_TEMPLATE.to_owned() }
+    }
+}
+
+/// Template with placeholders replaced
+pub fn render(template: &str, progress: &Progress, game: FnafWorldGame, slot: u8) -> String {
+    let mut text = template
+        .replace("{percent}", &format!("{:.0}%", progress.overall() * 100.0))
+        .replace("{game}", crate::export::game_to_str(game))
+        .replace("{slot}", &slot.to_string());
+    for c in &progress.categories {
+        let name = c.name.to_lowercase();
+        text = text
+            .replace(&format!("{{{name}}}"), &format!("{}/{}", c.done, c.total))
+            .replace(&format!("{{{name}_done}}"), &c.done.to_string())
+            .replace(&format!("{{{name}_total}}"), &c.total.to_string());
+    }
+    text
+}
+
+/// Contents of the overlay file
+pub fn contents(config: &OverlayConfig, progress: &Progress, game: FnafWorldGame, slot: u8) -> String {
+    let text = render(&config.template, progress, game, slot);
+    match config.format {
+        OverlayFormat::Text => text,
+        OverlayFormat::Json => {
+            let categories = progress.categories.iter()
+                .map(|c| (c.name.to_lowercase(), json!({ "done": c.done, "total": c.total })))
+                .collect::<Map<_, _>>();
+            let value = json!({
+                "game": crate::export::game_to_str(game),
+                "slot": slot,
+                "percent": (progress.overall() * 100.0).round(),
+                "categories": categories,
+                "text": text,
+            });
+            serde_json::to_string_pretty(&value).unwrap_or_default()
+        },
+        OverlayFormat::Html => format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<meta http-equiv=\"refresh\" content=\"2\">\n\
+            <style>body {{ margin: 0; color: white; font: 32px sans-serif; text-shadow: 0 0 4px black; }}</style>\n\
+            </head>\n<body>\n{text}\n</body>\n</html>\n"
+        ),
+    }
+}
+
+/// Slot loaded in the editor (starting from 1)
+static LOADED: Mutex<Option<(FnafWorldGame, u8)>> = Mutex::new(None);
+
+/// Remembers which slot to show and writes it
+pub fn set_loaded(game: FnafWorldGame, slot: u8) {
+    *LOADED.lock() = Some((game, slot));
+    changed(game, Some(slot));
+}
+
+/// Rewrites the overlay if the slot (none for trophies) is the loaded one or of its game
+pub fn changed(game: FnafWorldGame, slot: Option<u8>) {
+    let Some((loaded_game, loaded_slot)) = *LOADED.lock() else { return };
+    if game != loaded_game || slot.is_some_and(|s| s != loaded_slot) {
+        return;
+    }
+    if let Err(e) = refresh() {
+        eprintln!("Can't write the overlay: {e:#}");
+    }
+}
+
+/// Writes the overlay of the loaded slot as it is on disk. Returns the contents, none if the overlay is off
+pub fn refresh() -> anyhow::Result<Option<String>> {
+    let config = crate::config::CONFIG.lock().overlay.clone();
+    let Some((game, slot)) = *LOADED.lock() else { return Ok(None) };
+    if !config.enabled || config.path.as_os_str().is_empty() {
+        return Ok(None);
+    }
+    let save = SaveData::read(game, slot)?;
+    let info = InfoData::read(game)?;
+    let data = contents(&config, &Progress::new(&save, Some(&info)), game, slot);
+    write(&config.path, &data)?;
+    Ok(Some(data))
+}
+
+/// Replaces the file at once, so OBS never reads it half written
+fn write(path: &Path, data: &str) -> anyhow::Result<()> {
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, data)?;
+    std::fs::rename(tmp, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tokenizer::{test_info, test_save};
+
+    const GAME: FnafWorldGame = FnafWorldGame::WorldVanilla;
+
+    fn progress() -> Progress {
+        Progress::new(&test_save("[fnafw]\n1have=1\n1lv=4\n2have=1\nc1=1"), Some(&test_info("[info]\nbeatgame1=1")))
+    }
+
+    #[test]
+    fn renders_templates() {
+        let progress = progress();
+        assert_eq!(render(DEFAULT_TEMPLATE, &progress, GAME, 2), "characters 2/48, chips 1/21, endings 1/7");
+        assert_eq!(render("{game}:{slot} {chips_done} of {chips_total}", &progress, GAME, 2), "world:2 1 of 21");
+    }
+
+    #[test]
+    fn writes_json() {
+        let config = OverlayConfig { format: OverlayFormat::Json, ..Default::default() };
+        let json = serde_json::from_str::<serde_json::Value>(&contents(&config, &progress(), GAME, 2)).unwrap();
+        assert_eq!(json["categories"]["endings"]["done"], 1);
+        assert_eq!(json["text"], "characters 2/48, chips 1/21, endings 1/7");
+    }
+
+    #[test]
+    fn writes_html() {
+        let config = OverlayConfig { format: OverlayFormat::Html, template: "<b>{characters}</b>".to_owned(), ..Default::default() };
+        assert!(contents(&config, &progress(), GAME, 2).contains("<body>\n<b>2/48</b>\n</body>"));
+    }
+}
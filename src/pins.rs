@@ -0,0 +1,189 @@
+//! Pinned values: fields of a slot which are put back whenever the game writes the save without them.
+//! Pins are kept per slot in the config, every re-application is logged into `pins.log` of the config dir
+
+use std::io::Write as _;
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use crate::tokenizer::{construct_path, SaveData};
+use crate::Game as FnafWorldGame;
+
+/// Lines of the log sent to the ui
+pub const LOG_LINES: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PinField {
+    Tokens,
+    Pearls,
+    Armor,
+    /// Level of every owned character, as shown in the editor (starting from 1)
+    Levels,
+}
+
+impl PinField {
+    /// In the order of the ui
+    pub const ALL: [Self; 4] = [Self::Tokens, Self::Pearls, Self::Armor, Self::Levels];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Tokens => "tokens",
+            Self::Pearls => "pearls",
+            Self::Armor => "armor",
+            Self::Levels => "levels",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Pin {
+    pub field: PinField,
+    pub value: u32,
+    pub enabled: bool,
+}
+
+impl Pin {
+    pub fn holds(&self, save: &SaveData) -> bool {
+        match self.field {
+            PinField::Tokens => save.tokens == self.value,
+            PinField::Pearls => save.pearls == self.value,
+            PinField::Armor => save.armor == self.value,
+            PinField::Levels => save.characters.values().all(|c| c.lvl == self.value.saturating_sub(1)),
+        }
+    }
+
+    pub fn apply(&self, save: &mut SaveData) {
+        match self.field {
+            PinField::Tokens => save.tokens = self.value,
+            PinField::Pearls => save.pearls = self.value,
+            PinField::Armor => save.armor = self.value,
+            PinField::Levels => save.characters.values_mut().for_each(|c| c.lvl = self.value.saturating_sub(1)),
+        }
+    }
+}
+
+/// Pins of one slot
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SlotPins {
+    #[serde(with = "crate::export::game_name")]
+    pub game: FnafWorldGame,
+    /// Starting from 1
+    pub slot: u8,
+    pub pins: Vec<Pin>,
+}
+
+/// Pin of `field` in the slot, if it was ever set
+pub fn find(all: &[SlotPins], game: FnafWorldGame, slot: u8, field: PinField) -> Option<Pin> {
+    all.iter()
+        .find(|s| s.game == game && s.slot == slot)
+        .and_then(|s| s.pins.iter().find(|p| p.field == field).copied())
+}
+
+/// Replaces the pin of the same field in the slot
+pub fn set(all: &mut Vec<SlotPins>, game: FnafWorldGame, slot: u8, pin: Pin) {
+    let index = match all.iter().position(|s| s.game == game && s.slot == slot) {
+        Some(i) => i,
+        None => {
+            all.push(SlotPins { game, slot, pins: Vec::new() });
+            all.len() - 1
+        },
+    };
+    let pins = &mut all[index].pins;
+    pins.retain(|p| p.field != pin.field);
+    pins.push(pin);
+}
+
+/// Applies enabled pins which don't hold. Returns fields which were changed
+pub fn reapply(save: &mut SaveData, pins: &[Pin]) -> Vec<PinField> {
+    let mut changed = Vec::new();
+    for pin in pins.iter().filter(|p| p.enabled) {
+        if !pin.holds(save) {
+            pin.apply(save);
+            changed.push(pin.field);
+        }
+    }
+    changed
+}
+
+/// Checks the slot against its pins and writes it back if some didn't hold. Returns the log line if it did,
+/// which tells the write is held if the game is running
+pub fn enforce(game: FnafWorldGame, slot: u8) -> anyhow::Result<Option<String>> {
+    let pins = {
+        let config = crate::config::CONFIG.lock();
+        config.pins.iter().find(|s| s.game == game && s.slot == slot).map(|s| s.pins.clone()).unwrap_or_default()
+    };
+    if !pins.iter().any(|p| p.enabled) || !construct_path(game, slot - 1)?.exists() {
+        return Ok(None);
+    }
+    let mut save = SaveData::read(game, slot)?;
+    let fields = reapply(&mut save, &pins);
+    if fields.is_empty() {
+        return Ok(None);
+    }
+    // While the game runs the write only waits for it to exit
+    let held = crate::game_process::holds_writes();
+    save.save(crate::journal::Source::Pins)?;
+    let names = fields.iter().map(|f| f.name()).collect::<Vec<_>>().join(", ");
+    let action = if held { "held until the game exits" } else { "restored" };
+    let line = format!("{} {}:{slot}: {action} {names}", crate::timestamp::format(crate::timestamp::now()), crate::export::game_to_str(game));
+    log(&line)?;
+    Ok(Some(line))
+}
+
+fn log_path() -> PathBuf {
+    crate::config::config_dir().join("pins.log")
+}
+
+fn log(line: &str) -> anyhow::Result<()> {
+    std::fs::create_dir_all(crate::config::config_dir())?;
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(log_path())?;
+    writeln!(file, "{line}")?;
+    Ok(())
+}
+
+/// Last [`LOG_LINES`] of the log, newest first
+pub fn read_log() -> Vec<String> {
+    let log = std::fs::read_to_string(log_path()).unwrap_or_default();
+    log.lines().rev().take(LOG_LINES).map(str::to_owned).collect()
+}
+
+/// Enforces pins of every slot written by the game.
+/// `changed` is called with the slot and the log line after pins were put back (or held for the running game), or with the error
+pub fn start_watching(changed: impl Fn(FnafWorldGame, u8, anyhow::Result<String>) + Send + 'static) -> notify::Result<()> {
+    crate::save_file_watcher::subscribe(move |w| {
+        let Some(slot) = w.slot else { return };
+        if w.by_editor {
+            return;
+        }
+        match enforce(w.game, slot) {
+            Ok(None) => {},
+            Ok(Some(line)) => changed(w.game, slot, Ok(line)),
+            Err(e) => changed(w.game, slot, Err(e)),
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn reapplies_dropped_values() {
+        let mut save = SaveData::default();
+        save.tokens = 999999;
+        save.characters.insert(1, crate::tokenizer::Character { lvl: 98, next: 100 });
+        save.characters.insert(2, crate::tokenizer::Character { lvl: 4, next: 100 });
+        let mut all = Vec::new();
+        set(&mut all, FnafWorldGame::WorldVanilla, 2, Pin { field: PinField::Tokens, value: 999999, enabled: true });
+        set(&mut all, FnafWorldGame::WorldVanilla, 2, Pin { field: PinField::Levels, value: 99, enabled: true });
+        set(&mut all, FnafWorldGame::WorldVanilla, 2, Pin { field: PinField::Armor, value: 10, enabled: false });
+        set(&mut all, FnafWorldGame::WorldVanilla, 2, Pin { field: PinField::Armor, value: 10, enabled: true });
+        assert_eq!(all.len(), 1);
+        assert_eq!(all[0].pins.len(), 3);
+        assert_eq!(find(&all, FnafWorldGame::WorldVanilla, 2, PinField::Armor).map(|p| p.enabled), Some(true));
+        assert_eq!(find(&all, FnafWorldGame::WorldRefreshed, 2, PinField::Armor), None);
+
+        assert_eq!(reapply(&mut save, &all[0].pins), [PinField::Levels, PinField::Armor]);
+        assert_eq!((save.characters[&2].lvl, save.armor), (98, 10));
+        assert!(reapply(&mut save, &all[0].pins).is_empty());
+    }
+}
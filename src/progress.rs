@@ -0,0 +1,137 @@
+//! How much of the game is completed in a save
+
+use derive_more::Display;
+use crate::armor;
+use crate::catalog::{self, Kind};
+use crate::tokenizer::{InfoData, SaveData};
+use crate::Game as FnafWorldGame;
+
+pub const ENDINGS: u32 = 7;
+pub const CLOCKS: u32 = 5;
+
+/// Amount of chips available in the game
+pub fn chips_total(game: FnafWorldGame) -> u32 {
+    catalog::count(Kind::Chip, game)
+}
+
+/// Amount of bytes available in the game
+pub fn bytes_total(game: FnafWorldGame) -> u32 {
+    catalog::count(Kind::Byte, game)
+}
+
+/// Jumps which can be unlocked (first one is always available)
+pub fn jumps_total(game: FnafWorldGame) -> u32 {
+    match game {
+        FnafWorldGame::WorldVanilla => 5,
+        FnafWorldGame::WorldRefreshed => 6,
+    }
+}
+
+/// Guardian buttons including Porkpatch
+pub fn guardians_total(game: FnafWorldGame) -> u32 {
+    match game {
+        FnafWorldGame::WorldVanilla => 5,
+        FnafWorldGame::WorldRefreshed => 6,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Display)]
+#[display("{name}: {done}/{total}")]
+pub struct Category {
+    pub name: &'static str,
+    pub done: u32,
+    pub total: u32,
+    /// What the count stands for when it isn't a number of items, like the best armor piece
+    pub detail: Option<&'static str>,
+}
+
+impl Category {
+    fn new(name: &'static str, done: u32, total: u32) -> Self {
+        Self { name, done: done.min(total), total, detail: None }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Progress {
+    pub categories: Vec<Category>,
+}
+
+impl Progress {
+    pub fn new(save: &SaveData, info: Option<&InfoData>) -> Self {
+        let game = save.game();
+        let count = |v: &[bool]| v.iter().filter(|b| **b).count() as u32;
+        let jumps = jumps_total(game) as usize;
+        let guardians = guardians_total(game) as usize - 1;
+        let characters = catalog::count(Kind::Character, game);
+
+        let mut categories = vec![
+            Category::new("Characters", save.characters.keys().filter(|id| (1..=characters).contains(id)).count() as u32, characters),
+            Category::new("Chips", save.chips.iter().filter(|id| (1..=chips_total(game)).contains(id)).count() as u32, chips_total(game)),
+            Category::new("Bytes", save.bytes.iter().filter(|id| (1..=bytes_total(game)).contains(id)).count() as u32, bytes_total(game)),
+            Category::new("Jumps", count(&save.jumps[1..=jumps]), jumps_total(game)),
+            Category::new("Guardians", count(&save.guardians[..guardians]) + save.porkpatch_button as u32, guardians_total(game)),
+            Category::new("Clocks", count(&save.clocks_found), CLOCKS),
+            Category {
+                detail: armor::best_owned(&save.armor_owned).map(|p| p.name),
+                ..Category::new("Armor", armor::tier(&save.armor_owned), armor::PIECES.len() as u32)
+            },
+        ];
+        if let Some(info) = info {
+            categories.push(Category::new("Endings", info.endings.iter().filter(|e| (1..=ENDINGS).contains(e)).count() as u32, ENDINGS));
+        }
+        Self { categories }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Category> {
+        self.categories.iter().find(|c| c.name == name)
+    }
+
+    /// Share of everything done in all categories from 0.0 to 1.0. Every item counts the same,
+    /// so one of 5 clocks weighs as much as one of 48 characters
+    pub fn overall(&self) -> f32 {
+        let total = self.categories.iter().map(|c| c.total).sum::<u32>();
+        if total == 0 {
+            return 0.0;
+        }
+        self.categories.iter().map(|c| c.done).sum::<u32>() as f32 / total as f32
+    }
+
+    /// One line summary for slot list
+    pub fn summary(&self) -> String {
+        let characters = self.get("Characters").map(|c| format!(" · {}/{} chars", c.done, c.total)).unwrap_or_default();
+        format!("{:.0}% complete{characters}", self.overall() * 100.0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tokenizer::test_save;
+
+    #[test]
+    fn counts_categories() {
+        let data = "[fnafw]\n1have=1\n2have=1\n60have=1\nc1=1\nc2=1\np3=1\nsw1=1\nw3=1\nsw5=1\nsw7=1\ng2=1\nar1=1";
+        let save = test_save(data);
+        let mut info = InfoData::default();
+        info.endings.extend([1, 6]);
+        let progress = Progress::new(&save, Some(&info));
+
+        assert_eq!(progress.get("Characters").unwrap().done, 2);
+        assert_eq!(progress.get("Chips").unwrap().done, 2);
+        assert_eq!(progress.get("Bytes").unwrap().done, 1);
+        assert_eq!(progress.get("Jumps").unwrap().done, 2);
+        assert_eq!(progress.get("Guardians").unwrap().done, 2);
+        assert_eq!(progress.get("Clocks").unwrap().done, 1);
+        assert_eq!(progress.get("Armor").unwrap().done, 1);
+        assert_eq!(progress.get("Endings").unwrap().done, 2);
+        let total = progress.categories.iter().map(|c| c.total).sum::<u32>();
+        assert_eq!(progress.overall(), 13.0 / total as f32);
+    }
+
+    #[test]
+    fn armor_counts_best_piece() {
+        let save = test_save("[fnafw]\nar3=1");
+        let armor = Progress::new(&save, None).get("Armor").cloned().unwrap();
+        assert_eq!((armor.done, armor.total, armor.detail), (3, 3, Some("Blue Armor")));
+    }
+}
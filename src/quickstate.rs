@@ -0,0 +1,133 @@
+//! Practice states: numbered copies of a slot file which can be put back after every attempt.
+//! States of `fnafw2` live in `states/fnafw2/<number>.sav` of the config dir
+
+use std::cell::Cell;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use derive_more::Display;
+use crate::file_guard::{self, Seen};
+use crate::journal::Source;
+use crate::tokenizer::construct_path;
+use crate::Game as FnafWorldGame;
+
+pub const STATES: u8 = 9;
+/// How long the file must stay unchanged to be considered fully written
+const SETTLE: Duration = Duration::from_millis(200);
+
+#[derive(Debug, Display)]
+pub enum StateError {
+    #[display("State number must be from 1 to {STATES}, got {_0}")]
+    BadNumber(u8),
+    #[display("State {_0} is empty")]
+    Empty(u8),
+    #[display("Slot is empty, nothing to capture")]
+    NoSave,
+    #[display("Save file is being written right now. Try again in a moment")]
+    BeingWritten,
+}
+
+impl Error for StateError {}
+
+/// File of the slot (starting from 1) and the folder of its states
+fn paths(game: FnafWorldGame, slot: u8) -> anyhow::Result<(PathBuf, PathBuf)> {
+    let save = construct_path(game, slot.wrapping_sub(1))?;
+    let name = save.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    Ok((save, crate::config::config_dir().join("states").join(name)))
+}
+
+fn state_path(dir: &Path, number: u8) -> Result<PathBuf, StateError> {
+    if !(1..=STATES).contains(&number) {
+        return Err(StateError::BadNumber(number));
+    }
+    Ok(dir.join(format!("{number}.sav")))
+}
+
+/// Fails if size or modification time of the file changes within [`SETTLE`]
+fn ensure_settled(path: &Path) -> anyhow::Result<()> {
+    let stamp = |p: &Path| std::fs::metadata(p).map(|m| (m.len(), m.modified().ok())).ok();
+    let before = stamp(path);
+    std::thread::sleep(SETTLE);
+    if before != stamp(path) {
+        return Err(StateError::BeingWritten.into());
+    }
+    Ok(())
+}
+
+/// Copies the slot file into state `number`
+pub fn capture(game: FnafWorldGame, slot: u8, number: u8) -> anyhow::Result<()> {
+    let (save, dir) = paths(game, slot)?;
+    let state = state_path(&dir, number)?;
+    if !save.exists() {
+        return Err(StateError::NoSave.into());
+    }
+    ensure_settled(&save)?;
+    std::fs::create_dir_all(&dir)?;
+    std::fs::copy(save, state)?;
+    Ok(())
+}
+
+/// Puts state `number` back into the slot. Refuses while the slot file is being written
+pub fn restore(game: FnafWorldGame, slot: u8, number: u8, source: Source) -> anyhow::Result<()> {
+    let (save, dir) = paths(game, slot)?;
+    let state = state_path(&dir, number)?;
+    if !state.exists() {
+        return Err(StateError::Empty(number).into());
+    }
+    if save.exists() {
+        ensure_settled(&save)?;
+    }
+    let old = crate::changes::keys_of(game, Some(slot));
+    file_guard::write_bytes(save, std::fs::read(state)?, &Cell::new(Seen::Unknown))?;
+    crate::changes::written_file(source, game, Some(slot), &old);
+    Ok(())
+}
+
+pub fn delete(game: FnafWorldGame, slot: u8, number: u8) -> anyhow::Result<()> {
+    let (_, dir) = paths(game, slot)?;
+    let state = state_path(&dir, number)?;
+    if state.exists() {
+        std::fs::remove_file(state)?;
+    }
+    Ok(())
+}
+
+/// Unix time of capture for every state from 1 to [`STATES`], none for empty ones
+pub fn list(game: FnafWorldGame, slot: u8) -> anyhow::Result<Vec<Option<u64>>> {
+    let (_, dir) = paths(game, slot)?;
+    (1..=STATES).map(|n| {
+        let modified = std::fs::metadata(state_path(&dir, n)?).ok().and_then(|m| m.modified().ok());
+        Ok(modified.map(|t| t.duration_since(std::time::UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default()))
+    }).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn checks_numbers_and_writes() {
+        let dir = std::env::temp_dir();
+        assert!(matches!(state_path(&dir, 0), Err(StateError::BadNumber(0))));
+        assert!(matches!(state_path(&dir, 10), Err(StateError::BadNumber(10))));
+        assert!(state_path(&dir, 9).unwrap().ends_with("9.sav"));
+
+        let file = dir.join(format!("fnaf_state_test_{}", std::process::id()));
+        std::fs::write(&file, "[fnafw]").unwrap();
+        assert!(ensure_settled(&file).is_ok());
+        let writer = {
+            let file = file.clone();
+            std::thread::spawn(move || {
+                for i in 0..20 {
+                    std::fs::write(&file, "[fnafw]\n".repeat(i + 2)).unwrap();
+                    std::thread::sleep(Duration::from_millis(20));
+                }
+            })
+        };
+        std::thread::sleep(Duration::from_millis(30));
+        let err = ensure_settled(&file).unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(StateError::BeingWritten)));
+        writer.join().unwrap();
+        std::fs::remove_file(file).unwrap();
+    }
+}
@@ -0,0 +1,181 @@
+//! Reproducible random teams for challenge runs. Same seed and rules always give the same team in the same game
+
+use std::ops::RangeInclusive;
+use crate::catalog::{self, Kind};
+use crate::tokenizer::{Character, SaveData};
+use crate::Game as FnafWorldGame;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rules {
+    /// Party members, up to 8
+    pub party: usize,
+    /// Levels as shown in the game
+    pub levels: RangeInclusive<u32>,
+    /// Equipped chips, up to 4
+    pub chips: usize,
+    /// Equipped bytes, up to 4
+    pub bytes: usize,
+    /// Ids to choose chips from. `None` means every chip of the game
+    pub allowed_chips: Option<Vec<u32>>,
+    pub allowed_bytes: Option<Vec<u32>>,
+    /// Keep everything already unlocked with its levels instead of leaving only the random team
+    pub keep_progression: bool,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self {
+            party: 8,
+            levels: 1..=20,
+            chips: 4,
+            bytes: 4,
+            allowed_chips: None,
+            allowed_bytes: None,
+            keep_progression: true,
+        }
+    }
+}
+
+/// SplitMix64. Own generator so seeds keep giving the same teams between versions
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        ((self.next() as u128 * n as u128) >> 64) as u64
+    }
+
+    fn in_range(&mut self, range: &RangeInclusive<u32>) -> u32 {
+        let (lo, hi) = (*range.start(), (*range.end()).max(*range.start()));
+        lo + self.below((hi - lo) as u64 + 1) as u32
+    }
+
+    /// Takes `n` different items from `pool` in random order
+    fn choose(&mut self, mut pool: Vec<u32>, n: usize) -> Vec<u32> {
+        let n = n.min(pool.len());
+        for i in 0..n {
+            let j = i + self.below((pool.len() - i) as u64) as usize;
+            pool.swap(i, j);
+        }
+        pool.truncate(n);
+        pool
+    }
+}
+
+/// Numbers are used as is, any other text is hashed, so seeds like "stream-42" work too
+pub fn parse_seed(seed: &str) -> u64 {
+    let seed = seed.trim();
+    seed.parse().unwrap_or_else(|_| {
+        // FNV-1a
+        seed.bytes().fold(0xcbf29ce484222325, |h, b| (h ^ b as u64).wrapping_mul(0x100000001b3))
+    })
+}
+
+/// Fresh seed to show to the user
+pub fn new_seed() -> String {
+    let nanos = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_nanos();
+    (Rng(nanos as u64).next() % 1_000_000_000).to_string()
+}
+
+/// Parses comma separated ids and ranges (`1, 4-7`). Empty text means no restriction
+pub fn parse_ids(s: &str) -> anyhow::Result<Option<Vec<u32>>> {
+    if s.trim().is_empty() {
+        return Ok(None);
+    }
+    let mut ids = Vec::new();
+    for part in s.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        match part.split_once('-') {
+            Some((from, to)) => ids.extend(from.trim().parse::<u32>()?..=to.trim().parse()?),
+            None => ids.push(part.parse()?),
+        }
+    }
+    Ok(Some(ids))
+}
+
+fn pool(kind: Kind, game: FnafWorldGame, allowed: Option<&[u32]>) -> Vec<u32> {
+    catalog::entries(kind).iter()
+        .filter(|e| !e.refreshed_only || game == FnafWorldGame::WorldRefreshed)
+        .map(|e| e.id)
+        .filter(|id| allowed.is_none_or(|a| a.contains(id)))
+        .collect()
+}
+
+/// Gives `save` with a random team. Result depends only on the seed, the rules and the game of `save`
+pub fn randomize(save: &SaveData, seed: u64, rules: &Rules) -> SaveData {
+    let mut rng = Rng(seed);
+    let game = save.game();
+    let party = rng.choose(pool(Kind::Character, game, None), rules.party.min(8));
+    let levels = party.iter().map(|_| rng.in_range(&rules.levels)).collect::<Vec<_>>();
+    let chips = rng.choose(pool(Kind::Chip, game, rules.allowed_chips.as_deref()), rules.chips.min(4));
+    let bytes = rng.choose(pool(Kind::Byte, game, rules.allowed_bytes.as_deref()), rules.bytes.min(4));
+
+    let mut res = save.clone();
+    if !rules.keep_progression {
+        res.characters.clear();
+        res.chips.clear();
+        res.bytes.clear();
+    }
+    for (id, lvl) in party.iter().zip(levels) {
+        // Levels are stored one less than shown
+        res.characters.entry(*id).or_insert(Character { lvl: lvl.saturating_sub(1), next: 100 });
+    }
+    res.chips.extend(chips.iter().copied());
+    res.bytes.extend(bytes.iter().copied());
+
+    let fill = |slots: &mut [u32], ids: &[u32]| {
+        slots.fill(0);
+        slots[..ids.len()].copy_from_slice(ids);
+    };
+    fill(&mut res.selected_characters, &party);
+    fill(&mut res.selected_chips, &chips);
+    fill(&mut res.selected_bytes, &bytes);
+    res
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tokenizer::test_save;
+
+    fn rules() -> Rules {
+        Rules { party: 5, levels: 3..=7, chips: 3, allowed_chips: parse_ids("1-4, 10").unwrap(), ..Default::default() }
+    }
+
+    #[test]
+    fn same_seed_same_team() {
+        let save = test_save("[fnafw]\n1have=1\n1lv=50\nc3=1");
+        let a = randomize(&save, parse_seed("stream-42"), &rules());
+        assert!(crate::diff::diff_saves(&a, &randomize(&save, parse_seed("stream-42"), &rules())).is_empty());
+        assert_ne!(a.selected_characters, randomize(&save, parse_seed("43"), &rules()).selected_characters);
+    }
+
+    #[test]
+    fn follows_rules() {
+        let res = randomize(&test_save("[fnafw]\ntokens=0"), 7, &rules());
+        assert_eq!(res.selected_characters.iter().filter(|id| **id != 0).count(), 5);
+        assert!(res.selected_chips[..3].iter().all(|id| [1, 2, 3, 4, 10].contains(id)));
+        assert_eq!(res.selected_chips[3], 0);
+        assert!(res.selected_bytes.iter().all(|id| (1..=21).contains(id)));
+        // Shown levels 3 to 7 are stored as 2 to 6
+        assert!(res.selected_characters.iter().filter(|id| **id != 0).all(|id| (2..=6).contains(&res.characters[id].lvl)));
+    }
+
+    #[test]
+    fn keeps_progression_if_asked() {
+        let save = test_save("[fnafw]\n1have=1\n1lv=50\nc3=1");
+        let kept = randomize(&save, 7, &rules());
+        assert_eq!(kept.characters[&1].lvl, 50);
+        assert!(kept.chips.contains(&3));
+
+        let replaced = randomize(&save, 7, &Rules { keep_progression: false, ..rules() });
+        assert_eq!(replaced.characters.len(), 5);
+        assert_eq!(replaced.chips.len(), 3);
+    }
+}
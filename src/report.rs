@@ -0,0 +1,301 @@
+//! Printable summary of a slot and its trophies for bug reports and speedrun submissions,
+//! as Markdown or HTML with avatars embedded into the file
+
+use std::fmt::Write as _;
+use std::path::Path;
+use base64::Engine as _;
+use derive_more::Display;
+use crate::catalog::{self, Kind};
+use crate::locations::{Guardian, LOCATIONS};
+use crate::progress::{self, Progress};
+use crate::tokenizer::{Difficulty, GameMode, InfoData, SaveData};
+use crate::Game as FnafWorldGame;
+
+/// Avatars of characters 1 to 48, same as in the editor
+const AVATARS: [&[u8]; 48] = [
+    include_bytes!("../ui/assets/avatars/1.png"),
+    include_bytes!("../ui/assets/avatars/2.png"),
+    include_bytes!("../ui/assets/avatars/3.png"),
+    include_bytes!("../ui/assets/avatars/4.png"),
+    include_bytes!("../ui/assets/avatars/5.png"),
+    include_bytes!("../ui/assets/avatars/6.png"),
+    include_bytes!("../ui/assets/avatars/7.png"),
+    include_bytes!("../ui/assets/avatars/8.png"),
+    include_bytes!("../ui/assets/avatars/9.png"),
+    include_bytes!("../ui/assets/avatars/10.png"),
+    include_bytes!("../ui/assets/avatars/11.png"),
+    include_bytes!("../ui/assets/avatars/12.png"),
+    include_bytes!("../ui/assets/avatars/13.png"),
+    include_bytes!("../ui/assets/avatars/14.png"),
+    include_bytes!("../ui/assets/avatars/15.png"),
+    include_bytes!("../ui/assets/avatars/16.png"),
+    include_bytes!("../ui/assets/avatars/17.png"),
+    include_bytes!("../ui/assets/avatars/18.png"),
+    include_bytes!("../ui/assets/avatars/19.png"),
+    include_bytes!("../ui/assets/avatars/20.png"),
+    include_bytes!("../ui/assets/avatars/21.png"),
+    include_bytes!("../ui/assets/avatars/22.png"),
+    include_bytes!("../ui/assets/avatars/23.png"),
+    include_bytes!("../ui/assets/avatars/24.png"),
+    include_bytes!("../ui/assets/avatars/25.png"),
+    include_bytes!("../ui/assets/avatars/26.png"),
+    include_bytes!("../ui/assets/avatars/27.png"),
+    include_bytes!("../ui/assets/avatars/28.png"),
+    include_bytes!("../ui/assets/avatars/29.png"),
+    include_bytes!("../ui/assets/avatars/30.png"),
+    include_bytes!("../ui/assets/avatars/31.png"),
+    include_bytes!("../ui/assets/avatars/32.png"),
+    include_bytes!("../ui/assets/avatars/33.png"),
+    include_bytes!("../ui/assets/avatars/34.png"),
+    include_bytes!("../ui/assets/avatars/35.png"),
+    include_bytes!("../ui/assets/avatars/36.png"),
+    include_bytes!("../ui/assets/avatars/37.png"),
+    include_bytes!("../ui/assets/avatars/38.png"),
+    include_bytes!("../ui/assets/avatars/39.png"),
+    include_bytes!("../ui/assets/avatars/40.png"),
+    include_bytes!("../ui/assets/avatars/41.png"),
+    include_bytes!("../ui/assets/avatars/42.png"),
+    include_bytes!("../ui/assets/avatars/43.png"),
+    include_bytes!("../ui/assets/avatars/44.png"),
+    include_bytes!("../ui/assets/avatars/45.png"),
+    include_bytes!("../ui/assets/avatars/46.png"),
+    include_bytes!("../ui/assets/avatars/47.png"),
+    include_bytes!("../ui/assets/avatars/48.png"),
+];
+const UNKNOWN_AVATAR: &[u8] = include_bytes!("../ui/assets/avatars/unknown.png");
+
+#[derive(Debug, Display)]
+#[display("Unknown report format of \"{_0}\". Use .md or .html")]
+pub struct UnknownFormat(String);
+
+impl std::error::Error for UnknownFormat {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Markdown,
+    Html,
+}
+
+impl ReportFormat {
+    pub fn from_path(path: &Path) -> Result<Self, UnknownFormat> {
+        match path.extension().and_then(|e| e.to_str()).map(str::to_lowercase).as_deref() {
+            Some("md" | "markdown") => Ok(Self::Markdown),
+            Some("html" | "htm") => Ok(Self::Html),
+            _ => Err(UnknownFormat(path.display().to_string())),
+        }
+    }
+}
+
+/// Parts of the report, rendered by each format its own way
+enum Block {
+    Heading(String),
+    Text(String),
+    /// Name and value
+    Table(Vec<(String, String)>),
+    /// Character id and caption
+    Avatars(Vec<(u32, String)>),
+    List(Vec<String>),
+}
+
+fn avatar_uri(id: u32) -> String {
+    let png = id.checked_sub(1).and_then(|i| AVATARS.get(i as usize)).copied().unwrap_or(UNKNOWN_AVATAR);
+    format!("data:image/png;base64,{}", base64::engine::general_purpose::STANDARD.encode(png))
+}
+
+/// Named the same as in the editor, Refreshed has easy mode below normal
+fn difficulty_name(game: FnafWorldGame, diff: Difficulty) -> String {
+    match (game, diff.to_u32()) {
+        (FnafWorldGame::WorldVanilla, 1) | (FnafWorldGame::WorldRefreshed, 2) => "Normal".to_owned(),
+        (FnafWorldGame::WorldVanilla, 2) | (FnafWorldGame::WorldRefreshed, 3) => "Hard".to_owned(),
+        (FnafWorldGame::WorldRefreshed, 1) => "Easy".to_owned(),
+        (_, n) => format!("Unknown ({n})"),
+    }
+}
+
+fn yes_no(b: bool) -> String {
+    if b { "yes".to_owned() } else { "no".to_owned() }
+}
+
+/// Names of owned items, equipped ones marked
+fn items(kind: Kind, owned: &std::collections::HashSet<u32>, equipped: &[u32]) -> Vec<String> {
+    let mut ids = owned.iter().copied().collect::<Vec<_>>();
+    ids.sort_unstable();
+    ids.into_iter().map(|id| {
+        let mark = if equipped.contains(&id) { " (equipped)" } else { "" };
+        format!("#{id} {}{mark}", catalog::name(kind, id))
+    }).collect()
+}
+
+fn blocks(save: &SaveData, info: Option<&InfoData>, generated: u64) -> Vec<Block> {
+    let game = save.game();
+    let progress = Progress::new(save, info);
+    let count = |name: &str| progress.get(name).map(|c| format!(" ({}/{})", c.done, c.total)).unwrap_or_default();
+    let level = |id: u32| save.characters.get(&id).map_or(0, |c| c.lvl + 1);
+    let mut res = vec![
+        Block::Heading(format!("{} save report", crate::export::game_title(game))),
+        Block::Text(format!("Slot {}, generated {} UTC", save.slot(), crate::timestamp::format(generated))),
+        Block::Table(vec![
+            ("Completion".to_owned(), format!("{:.0}%", progress.overall() * 100.0)),
+            ("Playtime".to_owned(), format!("{}:{:02}:{:02}", save.time.0, save.time.1, save.time.2)),
+            ("Difficulty".to_owned(), difficulty_name(game, save.diff)),
+            ("Mode".to_owned(), match save.mode { GameMode::Adventure => "Adventure", GameMode::FixedParty => "Fixed party" }.to_owned()),
+            ("Tokens".to_owned(), save.tokens.to_string()),
+            ("Armor".to_owned(), match crate::armor::by_defence(save.armor) {
+                Some(piece) => piece.name.to_owned(),
+                None if save.armor == 0 => "None".to_owned(),
+                None => save.armor.to_string(),
+            }),
+        ]),
+    ];
+
+    res.push(Block::Heading("Party".to_owned()));
+    let party = save.selected_characters.iter().filter(|id| **id != 0)
+        .map(|id| (*id, format!("{}, level {}", catalog::name(Kind::Character, *id), level(*id))))
+        .collect::<Vec<_>>();
+    res.push(if party.is_empty() { Block::Text("Empty".to_owned()) } else { Block::Avatars(party) });
+
+    res.push(Block::Heading(format!("Characters{}", count("Characters"))));
+    let mut ids = save.characters.keys().copied().collect::<Vec<_>>();
+    ids.sort_unstable();
+    res.push(Block::Avatars(ids.into_iter().map(|id| (id, format!("{}, level {}", catalog::name(Kind::Character, id), level(id)))).collect()));
+
+    res.push(Block::Heading(format!("Chips{}", count("Chips"))));
+    res.push(Block::List(items(Kind::Chip, &save.chips, &save.selected_chips)));
+    res.push(Block::Heading(format!("Bytes{}", count("Bytes"))));
+    res.push(Block::List(items(Kind::Byte, &save.bytes, &save.selected_bytes)));
+
+    let places = LOCATIONS.iter().filter(|l| !l.refreshed_only || game == FnafWorldGame::WorldRefreshed);
+    let jumps = progress::jumps_total(game) as usize;
+    res.push(Block::Heading(format!("Jumps{}", count("Jumps"))));
+    res.push(Block::Table(places.clone().filter_map(|l| Some((l.name.to_owned(), l.jump.filter(|j| (1..=jumps).contains(j))?)))
+        .map(|(name, j)| (name, yes_no(save.jumps[j]))).collect()));
+    res.push(Block::Heading(format!("Guardians{}", count("Guardians"))));
+    res.push(Block::Table(places.clone().filter_map(|l| {
+        let beaten = match l.guardian? {
+            Guardian::Porkpatch => save.porkpatch_button,
+            Guardian::Button(i) => save.guardians.get(i).copied().unwrap_or_default(),
+        };
+        Some((l.name.to_owned(), yes_no(beaten)))
+    }).collect()));
+    res.push(Block::Heading(format!("Clocks{}", count("Clocks"))));
+    res.push(Block::Table(places.filter_map(|l| Some((l.name.to_owned(), yes_no(save.clocks_found[l.clock?])))).collect()));
+
+    if let Some(info) = info {
+        res.push(Block::Heading(format!("Endings{}", count("Endings"))));
+        res.push(Block::Table((1..=progress::ENDINGS).map(|e| (format!("Ending {e}"), yes_no(info.endings.contains(&e)))).collect()));
+        res.push(Block::Table(vec![("Pearl".to_owned(), yes_no(info.pearl)), ("Fan".to_owned(), yes_no(info.fan))]));
+    }
+    res
+}
+
+fn markdown(blocks: &[Block]) -> String {
+    let mut s = String::new();
+    for (i, block) in blocks.iter().enumerate() {
+        let _ = match block {
+            Block::Heading(text) => writeln!(s, "{} {text}\n", if i == 0 { "#" } else { "##" }),
+            Block::Text(text) => writeln!(s, "{text}\n"),
+            Block::Table(rows) => {
+                let _ = writeln!(s, "| | |\n|---|---|");
+                rows.iter().for_each(|(name, value)| { let _ = writeln!(s, "| {name} | {value} |"); });
+                writeln!(s)
+            },
+            Block::Avatars(characters) => {
+                let _ = writeln!(s, "| | Character |\n|---|---|");
+                for (id, caption) in characters {
+                    let _ = writeln!(s, "| ![{caption}]({}) | {caption} |", avatar_uri(*id));
+                }
+                writeln!(s)
+            },
+            Block::List(items) if items.is_empty() => writeln!(s, "None\n"),
+            Block::List(items) => {
+                items.iter().for_each(|item| { let _ = writeln!(s, "- {item}"); });
+                writeln!(s)
+            },
+        };
+    }
+    s
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn html(blocks: &[Block]) -> String {
+    let mut s = String::from(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>FNaF World save report</title>\n<style>\n\
+        body { font-family: sans-serif; max-width: 900px; margin: auto; }\n\
+        td { padding: 2px 12px 2px 0; }\n\
+        .avatars { display: flex; flex-wrap: wrap; gap: 12px; }\n\
+        figure { margin: 0; width: 100px; text-align: center; font-size: 13px; }\n\
+        figure img { width: 80px; height: 80px; image-rendering: pixelated; }\n\
+        </style>\n</head>\n<body>\n",
+    );
+    for (i, block) in blocks.iter().enumerate() {
+        let _ = match block {
+            Block::Heading(text) => writeln!(s, "<{0}>{1}</{0}>", if i == 0 { "h1" } else { "h2" }, escape(text)),
+            Block::Text(text) => writeln!(s, "<p>{}</p>", escape(text)),
+            Block::Table(rows) => {
+                s += "<table>\n";
+                rows.iter().for_each(|(name, value)| { let _ = writeln!(s, "<tr><td>{}</td><td>{}</td></tr>", escape(name), escape(value)); });
+                writeln!(s, "</table>")
+            },
+            Block::Avatars(characters) => {
+                s += "<div class=\"avatars\">\n";
+                for (id, caption) in characters {
+                    let caption = escape(caption);
+                    let _ = writeln!(s, "<figure><img src=\"{}\" alt=\"{caption}\"><figcaption>{caption}</figcaption></figure>", avatar_uri(*id));
+                }
+                writeln!(s, "</div>")
+            },
+            Block::List(items) if items.is_empty() => writeln!(s, "<p>None</p>"),
+            Block::List(items) => {
+                s += "<ul>\n";
+                items.iter().for_each(|item| { let _ = writeln!(s, "<li>{}</li>", escape(item)); });
+                writeln!(s, "</ul>")
+            },
+        };
+    }
+    s += "</body>\n</html>\n";
+    s
+}
+
+/// Report of the save and trophies of its game
+pub fn generate(save: &SaveData, info: Option<&InfoData>, format: ReportFormat) -> String {
+    let blocks = blocks(save, info, crate::timestamp::now());
+    match format {
+        ReportFormat::Markdown => markdown(&blocks),
+        ReportFormat::Html => html(&blocks),
+    }
+}
+
+/// Writes the report in the format of the file extension
+pub fn write(save: &SaveData, info: Option<&InfoData>, path: &Path) -> anyhow::Result<()> {
+    let format = ReportFormat::from_path(path)?;
+    std::fs::write(path, generate(save, info, format))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tokenizer::{test_save, test_info};
+
+    #[test]
+    fn renders_both_formats() {
+        let save = test_save("[fnafw]\n1have=1\n1lv=4\n60have=1\n60lv=0\ns1=1\ns2=60\nc1=1\nhour=2\nmin=5\nseconds=9");
+        let info = test_info("[info]\nbeatgame2=1");
+        let md = generate(&save, Some(&info), ReportFormat::Markdown);
+        assert!(md.starts_with("# FNaF World save report"));
+        assert!(md.contains("| Playtime | 2:05:09 |"));
+        assert!(md.contains("## Characters (1/48)"));
+        assert!(md.contains(&format!("]({}) |", avatar_uri(60))));
+        assert!(md.contains("| Ending 2 | yes |"));
+        assert_eq!(avatar_uri(60), format!("data:image/png;base64,{}", base64::engine::general_purpose::STANDARD.encode(UNKNOWN_AVATAR)));
+
+        let html = generate(&save, None, ReportFormat::Html);
+        assert!(html.contains("<h2>Party</h2>\n<div class=\"avatars\">"));
+        assert!(!html.contains("Ending"));
+        assert_eq!(ReportFormat::from_path(Path::new("a.HTML")).unwrap(), ReportFormat::Html);
+        assert!(ReportFormat::from_path(Path::new("a.txt")).is_err());
+    }
+}
@@ -0,0 +1,102 @@
+//! Notifications about slot files written in the saves folder, usually by the game itself
+
+use std::path::Path;
+use std::time::Duration;
+use notify::Watcher as _;
+use parking_lot::Mutex;
+use crate::tokenizer::{construct_path, SAVES_PATH};
+use crate::Game as FnafWorldGame;
+
+/// Time given to the game to finish writing before the save is reported
+const SETTLE: Duration = Duration::from_millis(300);
+
+/// Game and slot (starting from 1, none for trophies) of a save file name
+pub fn slot_of(path: &Path) -> Option<(FnafWorldGame, Option<u8>)> {
+    let name = path.file_name()?.to_str()?;
+    match name {
+        "info" => return Some((FnafWorldGame::WorldVanilla, None)),
+        "info1" => return Some((FnafWorldGame::WorldRefreshed, None)),
+        _ => {},
+    }
+    let (game, slot) = match name.strip_prefix("fnafwr") {
+        Some(slot) => (FnafWorldGame::WorldRefreshed, slot),
+        None => (FnafWorldGame::WorldVanilla, name.strip_prefix("fnafw")?),
+    };
+    let slot: u8 = slot.parse().ok()?;
+    construct_path(game, slot.wrapping_sub(1)).is_ok().then_some((game, Some(slot)))
+}
+
+/// Slot or trophies file written in the saves folder
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Written {
+    pub game: FnafWorldGame,
+    /// Starting from 1, none for trophies
+    pub slot: Option<u8>,
+    /// Written by this or another editor instance, not by the game
+    pub by_editor: bool,
+}
+
+type Subscriber = Box<dyn Fn(Written) + Send>;
+
+static SUBSCRIBERS: Mutex<Vec<Subscriber>> = Mutex::new(Vec::new());
+static WATCHING: Mutex<bool> = Mutex::new(false);
+
+/// Calls `changed` once for every slot or trophies file written in a burst. The folder is watched by one background thread
+/// shared by all subscribers, started by the first one
+pub fn subscribe(changed: impl Fn(Written) + Send + 'static) -> notify::Result<()> {
+    SUBSCRIBERS.lock().push(Box::new(changed));
+    let mut watching = WATCHING.lock();
+    if !*watching {
+        start()?;
+        *watching = true;
+    }
+    Ok(())
+}
+
+fn start() -> notify::Result<()> {
+    let (tx, rx) = std::sync::mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    watcher.watch(&SAVES_PATH, notify::RecursiveMode::NonRecursive)?;
+    std::thread::spawn(move || {
+        let _watcher = watcher;
+        while let Ok(event) = rx.recv() {
+            let mut paths = Vec::new();
+            let mut collect = |event: notify::Result<notify::Event>| {
+                if let Ok(e) = event && (e.kind.is_modify() || e.kind.is_create()) {
+                    paths.extend(e.paths);
+                }
+            };
+            collect(event);
+            // The game writes in several steps, wait for it to finish
+            std::thread::sleep(SETTLE);
+            rx.try_iter().for_each(&mut collect);
+
+            let mut written = Vec::new();
+            for path in paths {
+                let Some((game, slot)) = slot_of(&path) else { continue };
+                if !written.iter().any(|w: &Written| w.game == game && w.slot == slot) {
+                    written.push(Written { game, slot, by_editor: crate::file_guard::written_by_editor(&path) });
+                }
+            }
+            let subscribers = SUBSCRIBERS.lock();
+            for w in written {
+                subscribers.iter().for_each(|f| f(w));
+            }
+        }
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn recognizes_slot_files() {
+        assert_eq!(slot_of(&SAVES_PATH.join("fnafw3")), Some((FnafWorldGame::WorldVanilla, Some(3))));
+        assert_eq!(slot_of(&SAVES_PATH.join("fnafwr4")), Some((FnafWorldGame::WorldRefreshed, Some(4))));
+        assert_eq!(slot_of(&SAVES_PATH.join("fnafw4")), None);
+        assert_eq!(slot_of(&SAVES_PATH.join("info1")), Some((FnafWorldGame::WorldRefreshed, None)));
+        assert_eq!(slot_of(&SAVES_PATH.join("info2")), None);
+    }
+}
@@ -0,0 +1,206 @@
+//! Rhai scripts for edits presets don't cover. A script sees the slot as `save` and trophies of its game as `info`:
+//!
+//! ```rhai
+//! save.tokens = 999999;
+//! for id in save.characters() { save.set_level(id, 99); }
+//! save.unlock_chip(5); save.select_chip(5);
+//! save.set("mystery", 3);
+//! print(save.get("1lv"));
+//! ```
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use rhai::{Array, Dynamic, Engine, EvalAltResult, Scope};
+use crate::diff::{self, Change};
+use crate::journal::Source;
+use crate::tokenizer::{InfoData, SaveData};
+use crate::Game as FnafWorldGame;
+
+/// Stops scripts which loop forever
+const MAX_OPERATIONS: u64 = 10_000_000;
+
+/// Result of a script. Nothing is written by running it
+pub struct ScriptOutput {
+    pub save: SaveData,
+    pub info: InfoData,
+    /// Lines from `print` and `debug`
+    pub printed: Vec<String>,
+}
+
+fn to_u32(v: i64) -> u32 {
+    v.clamp(0, u32::MAX as i64) as u32
+}
+
+fn ids<'a>(ids: impl Iterator<Item = &'a u32>) -> Array {
+    let mut ids = ids.copied().collect::<Vec<_>>();
+    ids.sort_unstable();
+    ids.into_iter().map(|id| Dynamic::from_int(id as i64)).collect()
+}
+
+fn raw_value(v: Option<u32>) -> Dynamic {
+    v.map_or(Dynamic::UNIT, |v| Dynamic::from_int(v as i64))
+}
+
+fn script_error(e: impl std::fmt::Display) -> Box<EvalAltResult> {
+    e.to_string().into()
+}
+
+fn register_save(engine: &mut Engine) {
+    engine.register_type_with_name::<SaveData>("Save")
+        .register_get_set("tokens", |s: &mut SaveData| s.tokens as i64, |s: &mut SaveData, v: i64| s.tokens = to_u32(v))
+        .register_get_set("pearls", |s: &mut SaveData| s.pearls as i64, |s: &mut SaveData, v: i64| s.pearls = to_u32(v))
+        .register_get_set("armor", |s: &mut SaveData| s.armor as i64, |s: &mut SaveData, v: i64| s.armor = to_u32(v))
+        .register_get_set("x", |s: &mut SaveData| s.save_pos.0 as i64, |s: &mut SaveData, v: i64| s.save_pos.0 = to_u32(v))
+        .register_get_set("y", |s: &mut SaveData| s.save_pos.1 as i64, |s: &mut SaveData, v: i64| s.save_pos.1 = to_u32(v))
+        .register_get_set("hours", |s: &mut SaveData| s.time.0 as i64, |s: &mut SaveData, v: i64| s.time.0 = to_u32(v))
+        .register_get_set("minutes", |s: &mut SaveData| s.time.1 as i64, |s: &mut SaveData, v: i64| s.time.1 = to_u32(v))
+        .register_get_set("seconds", |s: &mut SaveData| s.time.2 as i64, |s: &mut SaveData, v: i64| s.time.2 = to_u32(v))
+        .register_get_set(
+            "party",
+            |s: &mut SaveData| s.selected_characters.iter().map(|id| Dynamic::from_int(*id as i64)).collect::<Array>(),
+            |s: &mut SaveData, party: Array| {
+                let party = party.into_iter().map(|v| v.as_int().map(to_u32)).collect::<Result<Vec<_>, _>>();
+                s.selected_characters = party.ok().and_then(|p| p.try_into().ok()).ok_or("Party must be 8 ids, 0 for empty places")?;
+                Ok::<_, Box<EvalAltResult>>(())
+            },
+        )
+        // Characters, levels are the same as shown in the editor (starting from 1)
+        .register_fn("characters", |s: &mut SaveData| ids(s.characters.keys()))
+        .register_fn("has_character", |s: &mut SaveData, id: i64| s.contains_character(to_u32(id)))
+        .register_fn("unlock_character", |s: &mut SaveData, id: i64| { s.edit_character(to_u32(id)); })
+        .register_fn("remove_character", |s: &mut SaveData, id: i64| s.remove_character(to_u32(id)))
+        .register_fn("level", |s: &mut SaveData, id: i64| s.characters.get(&to_u32(id)).map_or(0, |c| c.lvl as i64 + 1))
+        .register_fn("set_level", |s: &mut SaveData, id: i64, lvl: i64| s.edit_character(to_u32(id)).lvl = to_u32(lvl.saturating_sub(1)))
+        // Chips and bytes
+        .register_fn("chips", |s: &mut SaveData| ids(s.chips.iter()))
+        .register_fn("has_chip", |s: &mut SaveData, id: i64| s.chips.contains(&to_u32(id)))
+        .register_fn("unlock_chip", |s: &mut SaveData, id: i64| { s.chips.insert(to_u32(id)); })
+        .register_fn("lock_chip", |s: &mut SaveData, id: i64| {
+            s.chips.remove(&to_u32(id));
+            s.remove_selected_chip(to_u32(id));
+        })
+        .register_fn("select_chip", |s: &mut SaveData, id: i64| s.set_selected_chip(to_u32(id)))
+        .register_fn("deselect_chip", |s: &mut SaveData, id: i64| s.remove_selected_chip(to_u32(id)))
+        .register_fn("bytes", |s: &mut SaveData| ids(s.bytes.iter()))
+        .register_fn("has_byte", |s: &mut SaveData, id: i64| s.bytes.contains(&to_u32(id)))
+        .register_fn("unlock_byte", |s: &mut SaveData, id: i64| { s.bytes.insert(to_u32(id)); })
+        .register_fn("lock_byte", |s: &mut SaveData, id: i64| {
+            s.bytes.remove(&to_u32(id));
+            s.remove_selected_byte(to_u32(id));
+        })
+        .register_fn("select_byte", |s: &mut SaveData, id: i64| s.set_selected_byte(to_u32(id)))
+        .register_fn("deselect_byte", |s: &mut SaveData, id: i64| s.remove_selected_byte(to_u32(id)))
+        // Raw keys as written into the file
+        .register_fn("get", |s: &mut SaveData, key: &str| raw_value(s.raw_get(key)))
+        .register_fn("set", |s: &mut SaveData, key: &str, v: i64| s.raw_set(key, Some(to_u32(v))).map_err(script_error))
+        .register_fn("remove", |s: &mut SaveData, key: &str| s.raw_set(key, None).map_err(script_error));
+}
+
+fn register_info(engine: &mut Engine) {
+    engine.register_type_with_name::<InfoData>("Info")
+        .register_get_set("pearl", |i: &mut InfoData| i.pearl, |i: &mut InfoData, v: bool| i.pearl = v)
+        .register_get_set("fan", |i: &mut InfoData| i.fan, |i: &mut InfoData, v: bool| i.fan = v)
+        .register_fn("endings", |i: &mut InfoData| ids(i.endings.iter()))
+        .register_fn("has_ending", |i: &mut InfoData, n: i64| i.endings.contains(&to_u32(n)))
+        .register_fn("add_ending", |i: &mut InfoData, n: i64| { i.endings.insert(to_u32(n)); })
+        .register_fn("remove_ending", |i: &mut InfoData, n: i64| { i.endings.remove(&to_u32(n)); })
+        .register_fn("get", |i: &mut InfoData, key: &str| raw_value(i.raw_get(key)))
+        .register_fn("set", |i: &mut InfoData, key: &str, v: i64| i.raw_set(key, Some(to_u32(v))).map_err(script_error))
+        .register_fn("remove", |i: &mut InfoData, key: &str| i.raw_set(key, None).map_err(script_error));
+}
+
+/// Runs the script on copies of `save` and `info`
+pub fn run(script: &str, save: SaveData, info: InfoData) -> anyhow::Result<ScriptOutput> {
+    let printed = Rc::new(RefCell::new(Vec::new()));
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    register_save(&mut engine);
+    register_info(&mut engine);
+    let out = printed.clone();
+    engine.on_print(move |s| out.borrow_mut().push(s.to_owned()));
+    let out = printed.clone();
+    engine.on_debug(move |s, _, pos| out.borrow_mut().push(format!("{pos:?}: {s}")));
+
+    let mut scope = Scope::new();
+    scope.push_constant("game", crate::export::game_to_str(save.game()));
+    scope.push_constant("slot", save.slot() as i64);
+    scope.push("save", save);
+    scope.push("info", info);
+    engine.run_with_scope(&mut scope, script).map_err(|e| anyhow::anyhow!("Script failed: {e}"))?;
+
+    let Some(save) = scope.get_value::<SaveData>("save") else { anyhow::bail!("Script replaced `save` with something else") };
+    let Some(info) = scope.get_value::<InfoData>("info") else { anyhow::bail!("Script replaced `info` with something else") };
+    let printed = printed.take();
+    Ok(ScriptOutput { save, info, printed })
+}
+
+/// Script run on a slot, with what it would change there
+pub struct SlotRun {
+    pub output: ScriptOutput,
+    pub save_changes: Vec<Change>,
+    pub info_changes: Vec<Change>,
+}
+
+impl SlotRun {
+    pub fn changes(&self) -> impl Iterator<Item = &Change> {
+        self.save_changes.iter().chain(&self.info_changes)
+    }
+
+    /// Writes the save and trophies, each only if the script changed it
+    pub fn write(&self) -> anyhow::Result<()> {
+        if !self.save_changes.is_empty() {
+            self.output.save.save(Source::Script)?;
+        }
+        if !self.info_changes.is_empty() {
+            self.output.info.save(Source::Script)?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs the script on the slot (starting from 1) and trophies of its game. Nothing is written yet
+pub fn run_on_slot(script: &str, game: FnafWorldGame, slot: u8) -> anyhow::Result<SlotRun> {
+    let save = SaveData::read(game, slot)?;
+    let info = InfoData::read(game)?;
+    let output = run(script, save.clone(), info.clone())?;
+    Ok(SlotRun {
+        save_changes: diff::diff_saves(&save, &output.save),
+        info_changes: diff::diff_info(&info, &output.info),
+        output,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tokenizer::test_save;
+
+    #[test]
+    fn edits_copies_of_save_and_info() {
+        let save = test_save("[fnafw]\n1have=1\n1lv=4\n2have=1\nmystery=3\nother=1\ntokens=5");
+        let script = r#"
+            save.tokens = 999999;
+            for id in save.characters() { save.set_level(id, 99); }
+            save.unlock_chip(5);
+            save.select_chip(5);
+            print(save.get("mystery"));
+            save.set("mystery", 7);
+            save.remove("other");
+            save.remove_character(2);
+            info.add_ending(3);
+        "#;
+        let out = run(script, save.clone(), InfoData::default()).unwrap();
+        assert_eq!(out.printed, ["3"]);
+        assert_eq!(out.save.tokens, 999999);
+        assert_eq!(out.save.characters[&1].lvl, 98);
+        assert!(!out.save.contains_character(2));
+        assert_eq!(out.save.selected_chips[0], 5);
+        assert_eq!(out.save.raw_get("mystery"), Some(7));
+        assert_eq!(out.save.raw_get("other"), None);
+        assert!(out.info.endings.contains(&3));
+        assert_eq!(save.tokens, 5);
+
+        assert!(run("save.set(\"bad key\", 1);", save.clone(), InfoData::default()).is_err());
+        assert!(run("loop {}", save, InfoData::default()).is_err());
+    }
+}
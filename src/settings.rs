@@ -0,0 +1,120 @@
+//! Preferences of the editor and the session it restores on start, both kept in the config
+
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use crate::Game as FnafWorldGame;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Open the slot, tab and tool of the last run in the same window position
+    pub restore_session: bool,
+    /// Folder with saves instead of `AppData/Roaming/MMFApplications`, used after a restart
+    pub saves_dir: Option<PathBuf>,
+    pub backups: BackupPolicy,
+    /// Newest backups kept of every file, 0 keeps all
+    pub keep_backups: usize,
+    pub color_scheme: ColorScheme,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self { restore_session: true, saves_dir: None, backups: BackupPolicy::Replaced, keep_backups: 0, color_scheme: ColorScheme::System }
+    }
+}
+
+/// When files are copied into `backups` folder of the config dir
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackupPolicy {
+    /// Only before a file is replaced by an import, the library or a conflict overwrite
+    #[default]
+    Replaced,
+    /// Also before the first write of every file since the editor started
+    FirstWrite,
+}
+
+impl BackupPolicy {
+    /// In the order of the ui
+    pub const ALL: [Self; 2] = [Self::Replaced, Self::FirstWrite];
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColorScheme {
+    #[default]
+    System,
+    Light,
+    Dark,
+}
+
+impl ColorScheme {
+    /// In the order of the ui
+    pub const ALL: [Self; 3] = [Self::System, Self::Light, Self::Dark];
+}
+
+/// Where the editor was when it was closed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Session {
+    #[serde(with = "crate::export::game_name")]
+    pub game: FnafWorldGame,
+    /// Starting from 1, 0 for trophies
+    pub slot: u8,
+    /// Tab of the slot, empty if none was open
+    pub tab: String,
+    /// Empty if no tool was open
+    pub tool: String,
+    pub window: Option<WindowGeometry>,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self { game: FnafWorldGame::WorldVanilla, slot: 1, tab: String::new(), tool: String::new(), window: None }
+    }
+}
+
+/// Physical pixels
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WindowGeometry {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub maximized: bool,
+}
+
+impl Session {
+    /// Game and slot (starting from 1) of the save to load on start. Slot 1 when trophies were open
+    pub fn start_slot(&self, settings: &Settings) -> (FnafWorldGame, u8) {
+        if !settings.restore_session {
+            return (FnafWorldGame::WorldVanilla, 1);
+        }
+        let max = match self.game {
+            FnafWorldGame::WorldVanilla => 3,
+            FnafWorldGame::WorldRefreshed => 4,
+        };
+        (self.game, if (1..=max).contains(&self.slot) { self.slot } else { 1 })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn reads_partial_settings() {
+        let config = toml::from_str::<crate::config::Config>(
+            "[settings]\nbackups = \"first_write\"\n[session]\ngame = \"world_refreshed\"\nslot = 4\ntool = \"journal\""
+        ).unwrap();
+        assert!(config.settings.restore_session);
+        assert_eq!(config.settings.backups, BackupPolicy::FirstWrite);
+        assert_eq!(config.session.tool, "journal");
+        assert_eq!(config.session.start_slot(&config.settings), (FnafWorldGame::WorldRefreshed, 4));
+
+        let session = Session { slot: 0, ..config.session.clone() };
+        assert_eq!(session.start_slot(&config.settings), (FnafWorldGame::WorldRefreshed, 1));
+        let settings = Settings { restore_session: false, ..config.settings };
+        assert_eq!(config.session.start_slot(&settings), (FnafWorldGame::WorldVanilla, 1));
+    }
+}
@@ -0,0 +1,94 @@
+//! Short text codes for sending a save through chat. A code is `FW<version>-` followed by
+//! url-safe base64 of the deflated JSON export and its CRC32
+
+use std::error::Error;
+use std::io::{Read, Write};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use derive_more::Display;
+use flate2::Compression;
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use crate::export::{ExportFormat, SaveExport};
+use crate::tokenizer::{InfoData, SaveData};
+
+/// Version of the code layout. Layout of the export inside has its own version
+pub const CODE_VERSION: u32 = 1;
+const PREFIX: &str = "FW";
+
+#[derive(Debug, Display)]
+pub enum ShareCodeError {
+    #[display("This is not a save code")]
+    NotACode,
+    #[display("Code version {_0} is newer than supported version {CODE_VERSION}")]
+    UnsupportedVersion(u32),
+    #[display("Code is damaged. Make sure it was copied completely")]
+    Damaged,
+}
+
+impl Error for ShareCodeError {}
+
+pub fn encode(save: &SaveData, info: Option<&InfoData>) -> anyhow::Result<String> {
+    let json = serde_json::to_vec(&SaveExport::new(save, info))?;
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::best());
+    encoder.write_all(&json)?;
+    let mut data = encoder.finish()?;
+    data.extend(crc32fast::hash(&json).to_le_bytes());
+    Ok(format!("{PREFIX}{CODE_VERSION}-{}", URL_SAFE_NO_PAD.encode(data)))
+}
+
+/// Checks and unpacks the code. Whitespace inside is ignored, as chats like to break long lines
+pub fn decode(code: &str) -> anyhow::Result<SaveExport> {
+    let code = code.chars().filter(|c| !c.is_whitespace()).collect::<String>();
+    let (version, data) = code.strip_prefix(PREFIX)
+        .and_then(|c| c.split_once('-'))
+        .ok_or(ShareCodeError::NotACode)?;
+    let version: u32 = version.parse().map_err(|_| ShareCodeError::NotACode)?;
+    if version > CODE_VERSION {
+        return Err(ShareCodeError::UnsupportedVersion(version).into());
+    }
+
+    let data = URL_SAFE_NO_PAD.decode(data).map_err(|_| ShareCodeError::Damaged)?;
+    let (data, crc) = data.split_at_checked(data.len().saturating_sub(4)).ok_or(ShareCodeError::Damaged)?;
+    let mut json = String::new();
+    DeflateDecoder::new(data).read_to_string(&mut json).map_err(|_| ShareCodeError::Damaged)?;
+    if crc.len() != 4 || crc32fast::hash(json.as_bytes()).to_le_bytes() != crc {
+        return Err(ShareCodeError::Damaged.into());
+    }
+    SaveExport::from_str(&json, ExportFormat::Json)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::diff;
+    use crate::tokenizer::test_save;
+
+    fn code() -> (SaveData, String) {
+        let save = test_save("[fnafw]\n1have=1\n1lv=12\n2have=1\nc3=1\np5=1\ns1=2\ntokens=500\nar1=1\narmor=1");
+        let code = encode(&save, None).unwrap();
+        (save, code)
+    }
+
+    #[test]
+    fn round_trip() {
+        let (save, code) = code();
+        assert!(code.starts_with("FW1-"));
+        // Broken into lines by a chat
+        let wrapped = code.chars().collect::<Vec<_>>().chunks(20).map(|c| c.iter().collect::<String>()).collect::<Vec<_>>().join("\n");
+        let decoded = decode(&wrapped).unwrap();
+        assert!(decoded.info.is_none());
+        assert!(diff::diff_saves(&save, &decoded.save).is_empty());
+    }
+
+    #[test]
+    fn rejects_damaged_codes() {
+        let mut damaged = code().1.into_bytes();
+        let last = damaged.len() - 10;
+        damaged[last] = if damaged[last] == b'A' { b'B' } else { b'A' };
+        let err = decode(std::str::from_utf8(&damaged).unwrap()).unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(ShareCodeError::Damaged)));
+        assert!(matches!(decode("hello").unwrap_err().downcast_ref(), Some(ShareCodeError::NotACode)));
+        assert!(matches!(decode("FW9-AAAA").unwrap_err().downcast_ref(), Some(ShareCodeError::UnsupportedVersion(9))));
+    }
+}
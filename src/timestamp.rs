@@ -0,0 +1,47 @@
+//! Unix timestamps and their readable form, without pulling a date crate
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Seconds since unix epoch
+pub fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default()
+}
+
+/// `YYYY-MM-DD HH:MM` in UTC
+pub fn format(secs: u64) -> String {
+    let (days, rem) = (secs / 86400, secs % 86400);
+    // Civil from days, http://howardhinnant.github.io/date_algorithms.html
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02} {:02}:{:02}", rem / 3600, rem % 3600 / 60)
+}
+
+/// `YYYY-MM-DD HH:MM:SS` in UTC
+pub fn format_seconds(secs: u64) -> String {
+    format!("{}:{:02}", format(secs), secs % 60)
+}
+
+/// Same as [`format`], but usable in file names
+pub fn file_stamp(secs: u64) -> String {
+    format(secs).replace([' ', ':'], "-")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn formats_dates() {
+        assert_eq!(format(0), "1970-01-01 00:00");
+        assert_eq!(format(1_709_210_096), "2024-02-29 12:34");
+        assert_eq!(format_seconds(1_709_210_096), "2024-02-29 12:34:56");
+        assert_eq!(file_stamp(1_709_210_096), "2024-02-29-12-34");
+    }
+}
@@ -11,24 +11,44 @@ beatgame6 - Universe end
 beatgame7 - 
 */
 
+use std::cell::{Cell, RefCell};
 use std::collections::HashSet;
 use std::error::Error;
 use std::path::PathBuf;
 use std::sync::LazyLock;
-use std::{collections::HashMap, fs::File, io::Write};
+use std::collections::HashMap;
 use derive_more::Display;
+use serde::{Deserialize, Serialize};
 use slint::{VecModel, Weak};
+use crate::catalog::{self, Kind};
+use crate::discovery::RawKeys;
+use crate::file_guard::Seen;
+use crate::journal::Source;
 use crate::save_parser::{fnaf_world_parser, RawToken, TokenName};
 use crate::{Character as UICharacter, MainWindow};
 use crate::Game as FnafWorldGame;
 
+/// Folder with saves, [`crate::settings::Settings::saves_dir`] if it's set
 pub static SAVES_PATH: LazyLock<PathBuf> = LazyLock::new(|| {
-    let mut home = home::home_dir().expect("Failed to find the home directory");
-    home.extend(["AppData","Roaming","MMFApplications"]);
+    let custom = crate::config::CONFIG.lock().settings.saves_dir.clone();
+    if let Some(dir) = custom {
+        match std::fs::create_dir_all(&dir) {
+            Ok(()) => return dir,
+            Err(e) => eprintln!("Can't use saves folder {}: {e}", dir.display()),
+        }
+    }
+    let home = default_saves_dir();
     std::fs::create_dir_all(&home).expect("Unable to create save directory if it not exists");
     home
 });
 
+/// Where the game keeps its saves
+pub fn default_saves_dir() -> PathBuf {
+    let mut home = home::home_dir().expect("Failed to find the home directory");
+    home.extend(["AppData","Roaming","MMFApplications"]);
+    home
+}
+
 pub fn construct_path(game: FnafWorldGame, slot: u8) -> Result<PathBuf, SlotError> {
     let mut path = PathBuf::clone(&SAVES_PATH);
     match game {
@@ -50,11 +70,27 @@ pub fn construct_path(game: FnafWorldGame, slot: u8) -> Result<PathBuf, SlotErro
             }
         }
     }
-    println!("{:?}", &path);
     Ok(path)
 }
 
-#[derive(Debug, Default, Display, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+/// Contents the file is going to have: held for the running game if it was written meanwhile, otherwise as on disk
+fn read_current(path: &std::path::Path) -> std::io::Result<String> {
+    match crate::game_process::held(path) {
+        Some(data) => Ok(String::from_utf8_lossy(&data).into_owned()),
+        None => std::fs::read_to_string(path),
+    }
+}
+
+pub fn info_path(game: FnafWorldGame) -> PathBuf {
+    let mut path = SAVES_PATH.clone();
+    match game {
+        FnafWorldGame::WorldVanilla => path.push("info"),
+        FnafWorldGame::WorldRefreshed => path.push("info1"),
+    }
+    path
+}
+
+#[derive(Debug, Default, Display, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum Token {
     #[display("{_0}have=1")]
     CharId(u32),
@@ -97,10 +133,10 @@ pub enum Token {
     X(u32),
     #[display("y={_0}")]
     Y(u32),
-    // defence // 10 e.q. 10 represents the best armor +100 defence
+    // Defence of equipped armor divided by 10, e.q. 10 represents the best armor +100 defence
     #[display("armor={_0}")]
     Armor(u32),
-    // Id of armor bought
+    // Id of armor piece bought, one token for every piece
     #[display("ar{_0}=1")]
     Ar(u32),
     // Had ending, number corresponds to different endings
@@ -216,7 +252,7 @@ impl From<RawToken> for Token {
 }
 
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Character {
     pub lvl: u32,
     pub next: u32,
@@ -228,7 +264,7 @@ impl Default for Character {
     }
 }
 
-#[derive(Debug, Display, Default, Clone, Copy)]
+#[derive(Debug, Display, Default, Clone, Copy, Serialize, Deserialize)]
 pub enum GameMode {
     #[default]
     #[display("mode=1")]
@@ -267,7 +303,7 @@ impl TryFrom<u32> for GameMode {
     }
 }
 
-#[derive(Debug, Display, Default, Clone, Copy)]
+#[derive(Debug, Display, Default, Clone, Copy, Serialize, Deserialize)]
 pub enum Difficulty {
     #[default]
     #[display("diff=1")]
@@ -308,12 +344,23 @@ newgame=0
 started=1
 locked=1
 */
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct SaveData {
+    #[serde(skip)]
     game: FnafWorldGame,
+    #[serde(skip)]
     slot: u8,
+    /// File contents this copy is based on
+    #[serde(skip)]
+    disk: Cell<Seen>,
+    /// Keys of these contents, none if they aren't known
+    #[serde(skip)]
+    disk_keys: RefCell<Option<RawKeys>>,
+    #[serde(with = "crate::export::ordered_map")]
     pub characters: HashMap<u32, Character>,
+    #[serde(serialize_with = "crate::export::ordered_set")]
     pub chips: HashSet<u32>,
+    #[serde(serialize_with = "crate::export::ordered_set")]
     pub bytes: HashSet<u32>,
     pub selected_characters: [u32; 8],
     pub selected_chips: [u32; 4],
@@ -330,7 +377,10 @@ pub struct SaveData {
     pub clocks_found: [bool; 5],
     pub jumps: [bool; 7],
     pub guardians: [bool; 5],
-    pub armor_id: u32,
+    /// Ids of bought armor pieces, see [`crate::armor`]
+    #[serde(alias = "armor_id", serialize_with = "crate::export::ordered_set", deserialize_with = "crate::armor::deserialize_owned")]
+    pub armor_owned: HashSet<u32>,
+    #[serde(serialize_with = "crate::export::ordered_set")]
     pub flags: HashSet<Token>,
     pub porkpatch_button: bool,
 }
@@ -358,20 +408,31 @@ where I: Iterator<Item = Token>
                 Token::Minutes(count) => res.time.1 = count,
                 Token::Seconds(count) => res.time.2 = count,
                 Token::Tokens(count) => res.tokens = count,
-                Token::Slot { slot, id } => { if (slot as usize) < res.selected_characters.len() + 1 {res.selected_characters[slot as usize - 1] = id} },
-                Token::ByteSlot { slot, id } => { if (slot as usize) < res.selected_bytes.len() + 1 {res.selected_bytes[slot as usize - 1] = id} },
-                Token::ChipSlot { slot, id } => { if (slot as usize) < res.selected_chips.len() + 1 {res.selected_chips[slot as usize - 1] = id} },
+                // Places which don't exist in vanilla are kept as is
+                Token::Slot { slot, id } => match slot_mut(&mut res.selected_characters, slot) {
+                    Some(s) => *s = id,
+                    None => { res.flags.insert(t); },
+                },
+                Token::ByteSlot { slot, id } => match slot_mut(&mut res.selected_bytes, slot) {
+                    Some(s) => *s = id,
+                    None => { res.flags.insert(t); },
+                },
+                Token::ChipSlot { slot, id } => match slot_mut(&mut res.selected_chips, slot) {
+                    Some(s) => *s = id,
+                    None => { res.flags.insert(t); },
+                },
                 Token::Mode(value) => res.mode = GameMode::try_from(value).unwrap_or_default(),
                 Token::Diff(value) => res.diff = Difficulty::from(value as i32),
                 Token::X(value) => res.save_pos.0 = value,
                 Token::Y(value) => res.save_pos.1 = value,
                 Token::Armor(value) => res.armor = value,
-                Token::Ar(value) => res.armor_id = value,
+                Token::Ar(id) => { res.armor_owned.insert(id); },
                 Token::BeatGame(_value) => {},
                 Token::Cine(value) => res.dialog = value,
                 Token::Find(value) => res.clock_spawned = value,
-                Token::ClockFound(id) => {
-                    if id < res.clocks_found.len() as u32 + 1 {res.clocks_found[id as usize - 1] = true}
+                Token::ClockFound(id) => match slot_mut(&mut res.clocks_found, id) {
+                    Some(c) => *c = true,
+                    None => { res.flags.insert(t); },
                 },
                 Token::SW(id) => match id {
                     1 => res.jumps[1] = true,
@@ -395,6 +456,55 @@ where I: Iterator<Item = Token>
     }
 }
 
+/// Length of ui list for ids of `kind`: everything known for the game and everything present in the save
+fn ui_len<'a>(kind: Kind, game: FnafWorldGame, ids: impl Iterator<Item = &'a u32>) -> usize {
+    ids.copied()
+        .filter(|id| *id <= catalog::MAX_UI_ID)
+        .chain([catalog::count(kind, game)])
+        .max()
+        .unwrap_or_default() as usize
+}
+
+/// List of `len` flags with `ids` (starting from 1) set. Ids out of range are skipped
+fn ui_flags<'a>(len: usize, ids: impl Iterator<Item = &'a u32>) -> Vec<bool> {
+    let mut v = vec![false; len];
+    for id in ids {
+        if let Some(b) = id.checked_sub(1).and_then(|i| v.get_mut(i as usize)) {
+            *b = true;
+        }
+    }
+    v
+}
+
+/// Place in `places` for 1-based `slot`, if there is one
+fn slot_mut<T>(places: &mut [T], slot: u32) -> Option<&mut T> {
+    slot.checked_sub(1).and_then(|i| places.get_mut(i as usize))
+}
+
+/// Keys and values of parsed `tokens`, the last one wins
+fn raw_keys(tokens: &[RawToken]) -> RawKeys {
+    tokens.iter().map(|t| (t.name.to_string(), t.value)).collect()
+}
+
+fn raw_get(text: &str, key: &str) -> Option<u32> {
+    fnaf_world_parser(text).ok()?.into_iter().rev().find(|t| t.name.to_string() == key).map(|t| t.value)
+}
+
+/// Raw tokens of `text` with keys replaced one after another
+fn raw_set(text: &str, values: &[(&str, Option<u32>)]) -> anyhow::Result<Vec<RawToken>> {
+    let mut tokens = fnaf_world_parser(text)?;
+    for (key, value) in values {
+        tokens.retain(|t| t.name.to_string() != *key);
+        if let Some(value) = value {
+            match fnaf_world_parser(&format!("[key]\n{key}={value}"))?.pop() {
+                Some(t) if t.name.to_string() == *key => tokens.push(t),
+                _ => anyhow::bail!("\"{key}\" is not a valid key"),
+            }
+        }
+    }
+    Ok(tokens)
+}
+
 #[derive(Debug, Display)]
 #[display("Wrong slot: {_0}")]
 pub struct SlotError(u8);
@@ -402,20 +512,24 @@ pub struct SlotError(u8);
 impl Error for SlotError {}
 
 impl SaveData {
-    pub fn save(&self) -> anyhow::Result<()> {
+    pub fn save(&self, source: Source) -> anyhow::Result<()> {
         if crate::REJECT_SAVE.load(std::sync::atomic::Ordering::Acquire) {
             return Ok(());
         }
 
-        #[cfg(debug_assertions)]
-        {
-            static NUM: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
-            NUM.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-            println!("Begin save {}", NUM.load(std::sync::atomic::Ordering::Relaxed));
-        }
-
         let path = construct_path(self.game, self.slot)?;
+        let text = self.to_text();
+        let new = crate::discovery::parse_keys(&text)?;
+        let old = self.disk_keys.borrow().clone().unwrap_or_else(|| crate::changes::keys_of(self.game, Some(self.slot())));
+        crate::file_guard::write(path, text, &self.disk)?;
+        crate::changes::written(source, self.game, Some(self.slot()), &old, &new);
+        *self.disk_keys.borrow_mut() = Some(new);
 
+        Ok(())
+    }
+
+    /// Contents of the save file
+    pub fn to_text(&self) -> String {
         let mut tokens = Vec::new();
 
         tokens.push(Token::Hour(self.time.0));
@@ -443,8 +557,8 @@ impl SaveData {
         }
         tokens.push(Token::Cine(self.dialog));
         tokens.push(Token::Armor(self.armor));
-        for i in 0..self.armor_id {
-            tokens.push(Token::Ar(i+1));
+        for id in self.armor_owned.iter() {
+            tokens.push(Token::Ar(*id));
         }
         tokens.push(Token::Pearl(self.pearls));
         tokens.push(Token::Tokens(self.tokens));
@@ -471,55 +585,95 @@ impl SaveData {
             self.flags.iter().cloned()
         );
 
-        let mut file = File::create(path)?;
         let data = tokens.into_iter().map(|t| t.to_string()).collect::<Vec<_>>().join("\n");
-        let data = format!("[fnafw]\n{}\nnewgame=0\nstarted=1\nlocked=1\n", data);
-        file.write_all(data.as_bytes())?;
+        format!("[fnafw]\n{}\nnewgame=0\nstarted=1\nlocked=1\n", data)
+    }
+
+    /// Value of a raw key as it would be written into the file
+    pub fn raw_get(&self, key: &str) -> Option<u32> {
+        raw_get(&self.to_text(), key)
+    }
+
+    /// Sets or removes (`None`) a raw key. Keys the editor knows end up in their fields
+    pub fn raw_set(&mut self, key: &str, value: Option<u32>) -> anyhow::Result<()> {
+        self.raw_set_all(&[(key, value)])
+    }
 
+    /// Same as [`SaveData::raw_set`] for several keys at once, so keys of one field (e.g. `1have`, `1lv`, `1next`) can be removed together
+    pub fn raw_set_all(&mut self, values: &[(&str, Option<u32>)]) -> anyhow::Result<()> {
+        let tokens = raw_set(&self.to_text(), values)?;
+        *self = Self { game: self.game, slot: self.slot, disk: self.disk.clone(), disk_keys: self.disk_keys.clone(), ..Self::from(tokens.into_iter().map(Token::from)) };
         Ok(())
     }
 
     pub fn read(game: FnafWorldGame, slot: u8) -> anyhow::Result<Self> {
-        let path = construct_path(game, slot - 1)?;
-        let data = match std::fs::read_to_string(path) {
+        let path = construct_path(game, slot.checked_sub(1).ok_or(SlotError(slot))?)?;
+        let data = match read_current(&path) {
             Ok(s) => s,
             Err(_) => {
-                let save = Self { game, slot: slot - 1, characters: HashMap::from_iter((0..8).map(|id| (id+1, Character{ lvl: 0, next: 100 }))), ..Default::default()};
+                let save = Self { game, slot: slot - 1, disk: Cell::new(Seen::Missing), disk_keys: RefCell::new(Some(RawKeys::new())), characters: HashMap::from_iter((0..8).map(|id| (id+1, Character{ lvl: 0, next: 100 }))), ..Default::default()};
                 return Ok(save);
             }
         };
         let tokens = fnaf_world_parser(&data)?;
+        let keys = raw_keys(&tokens);
         let mut res = Self::from(tokens.into_iter().map(Token::from));
         res.game = game;
         res.slot = slot - 1;
+        res.disk.set(Seen::of(Some(data.as_bytes())));
+        res.disk_keys = RefCell::new(Some(keys));
 
         Ok(res)
     }
 
+    /// Reads save from arbitrary file. Game and slot are left default
+    pub fn read_path(path: &std::path::Path) -> anyhow::Result<Self> {
+        let data = std::fs::read_to_string(path)?;
+        let tokens = fnaf_world_parser(&data)?;
+        Ok(Self::from(tokens.into_iter().map(Token::from)))
+    }
+
+    pub fn game(&self) -> FnafWorldGame {
+        self.game
+    }
+
+    /// Slot number starting from 1 (same as in [`SaveData::read`])
+    pub fn slot(&self) -> u8 {
+        self.slot + 1
+    }
+
+    /// Moves save to another game/slot (starting from 1). Next [`SaveData::save`] will write there
+    pub fn relocate(&mut self, game: FnafWorldGame, slot: u8) -> Result<(), SlotError> {
+        let slot = slot.checked_sub(1).ok_or(SlotError(slot))?;
+        construct_path(game, slot)?;
+        self.game = game;
+        self.slot = slot;
+        self.disk.set(Seen::Unknown);
+        self.disk_keys.take();
+        Ok(())
+    }
+
+    /// Lets the next [`SaveData::save`] overwrite the file even if it was changed by another program
+    pub fn ignore_changes_on_disk(&self) {
+        self.disk.set(Seen::Unknown);
+        self.disk_keys.take();
+    }
+
     pub fn get_characters_ui(&self) -> Vec<UICharacter> {
-        let mut characters = vec![UICharacter::default(); 48];
+        let mut characters = vec![UICharacter::default(); ui_len(Kind::Character, self.game, self.characters.keys().chain(&self.selected_characters))];
         for (k, v) in self.characters.iter() {
-            characters[*k as usize - 1].activated = true;
-            characters[*k as usize - 1].lvl = v.lvl as i32 + 1;
-            characters[*k as usize - 1].next = v.next as i32;
+            if let Some(c) = k.checked_sub(1).and_then(|i| characters.get_mut(i as usize)) {
+                c.activated = true;
+                c.lvl = v.lvl as i32 + 1;
+                c.next = v.next as i32;
+            }
         }
         characters
     }
 
     pub fn get_chips_ui(&self) -> (Vec<bool>, Vec<bool>) {
-        let mut v = vec![false; 26];
-        for c in self.chips.iter() {
-            v[*c as usize - 1] = true;
-        }
-        let mut selected = vec![false; 26];
-        for sel in self.selected_chips.iter() {
-            if *sel != 0 {
-                if let Some(s) = selected.get_mut((*sel - 1) as usize) {
-                    *s = true;
-                }
-            }
-        }
-        (v, selected)
+        let len = ui_len(Kind::Chip, self.game, self.chips.iter().chain(&self.selected_chips));
+        (ui_flags(len, self.chips.iter()), ui_flags(len, self.selected_chips.iter()))
     }
 
     pub fn set_selected_chip(&mut self, id: u32) {
@@ -542,19 +696,8 @@ impl SaveData {
     }
 
     pub fn get_bytes_ui(&self) -> (Vec<bool>, Vec<bool>) {
-        let mut v = vec![false; 26];
-        for c in self.bytes.iter() {
-            v[*c as usize - 1] = true;
-        }
-        let mut selected = vec![false; 26];
-        for sel in self.selected_bytes.iter() {
-            if *sel != 0 {
-                if let Some(s) = selected.get_mut((*sel - 1) as usize) {
-                    *s = true;
-                }
-            }
-        }
-        (v, selected)
+        let len = ui_len(Kind::Byte, self.game, self.bytes.iter().chain(&self.selected_bytes));
+        (ui_flags(len, self.bytes.iter()), ui_flags(len, self.selected_bytes.iter()))
     }
 
     pub fn set_selected_byte(&mut self, id: u32) {
@@ -589,9 +732,17 @@ impl SaveData {
     }
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct InfoData {
+    #[serde(skip)]
     game: FnafWorldGame,
+    /// File contents this copy is based on
+    #[serde(skip)]
+    disk: Cell<Seen>,
+    /// Keys of these contents, none if they aren't known
+    #[serde(skip)]
+    disk_keys: RefCell<Option<RawKeys>>,
+    #[serde(serialize_with = "crate::export::ordered_set")]
     pub endings: HashSet<u32>,
     pub pearl: bool,
     pub fan: bool,
@@ -617,30 +768,40 @@ where I: Iterator<Item = Token>
 
 impl InfoData {
     pub fn read(game: FnafWorldGame) -> anyhow::Result<Self> {
-        let path = match game {
-            FnafWorldGame::WorldVanilla => {
-                let mut path = SAVES_PATH.clone();
-                path.push("info");
-                path
-            },
-            FnafWorldGame::WorldRefreshed => {
-                let mut path = SAVES_PATH.clone();
-                path.push("info1");
-                path
-            },
-        };
-        let data = match std::fs::read_to_string(path) {
+        let data = match read_current(&info_path(game)) {
             Ok(s) => s,
-            Err(_) => return Ok(Self { game, ..Default::default() })
+            Err(_) => return Ok(Self { game, disk: Cell::new(Seen::Missing), disk_keys: RefCell::new(Some(RawKeys::new())), ..Default::default() })
         };
 
         let tokens = fnaf_world_parser(&data)?;
+        let keys = raw_keys(&tokens);
         let mut res = Self::from(tokens.into_iter().map(Token::from));
         res.game = game;
+        res.disk.set(Seen::of(Some(data.as_bytes())));
+        res.disk_keys = RefCell::new(Some(keys));
         Ok(res)
     }
 
-    pub fn save(&self) -> anyhow::Result<()> {
+    /// Reads info from arbitrary file. Game is left default
+    pub fn read_path(path: &std::path::Path) -> anyhow::Result<Self> {
+        let data = std::fs::read_to_string(path)?;
+        let tokens = fnaf_world_parser(&data)?;
+        Ok(Self::from(tokens.into_iter().map(Token::from)))
+    }
+
+    pub fn save(&self, source: Source) -> anyhow::Result<()> {
+        let text = self.to_text();
+        let new = crate::discovery::parse_keys(&text)?;
+        let old = self.disk_keys.borrow().clone().unwrap_or_else(|| crate::changes::keys_of(self.game, None));
+        crate::file_guard::write(info_path(self.game), text, &self.disk)?;
+        crate::changes::written(source, self.game, None, &old, &new);
+        *self.disk_keys.borrow_mut() = Some(new);
+
+        Ok(())
+    }
+
+    /// Contents of the info file
+    pub fn to_text(&self) -> String {
         let mut tokens = Vec::new();
 
         for i in self.endings.iter() {
@@ -654,26 +815,43 @@ impl InfoData {
         }
         tokens.extend(self.other.iter().cloned());
 
-        let path = match self.game {
-            FnafWorldGame::WorldVanilla => {
-                let mut path = SAVES_PATH.clone();
-                path.push("info");
-                path
-            },
-            FnafWorldGame::WorldRefreshed => {
-                let mut path = SAVES_PATH.clone();
-                path.push("info1");
-                path
-            },
-        };
-        let mut file = File::create(path)?;
         let data = tokens.into_iter().map(|t| t.to_string()).collect::<Vec<_>>().join("\n");
-        let data = format!("[info]\n{}\n", data);
-        file.write_all(data.as_bytes())?;
+        format!("[info]\n{}\n", data)
+    }
 
+    /// Same as [`SaveData::raw_get`]
+    pub fn raw_get(&self, key: &str) -> Option<u32> {
+        raw_get(&self.to_text(), key)
+    }
+
+    /// Same as [`SaveData::raw_set`]
+    pub fn raw_set(&mut self, key: &str, value: Option<u32>) -> anyhow::Result<()> {
+        self.raw_set_all(&[(key, value)])
+    }
+
+    /// Same as [`SaveData::raw_set_all`]
+    pub fn raw_set_all(&mut self, values: &[(&str, Option<u32>)]) -> anyhow::Result<()> {
+        let tokens = raw_set(&self.to_text(), values)?;
+        *self = Self { game: self.game, disk: self.disk.clone(), disk_keys: self.disk_keys.clone(), ..Self::from(tokens.into_iter().map(Token::from)) };
         Ok(())
     }
 
+    pub fn game(&self) -> FnafWorldGame {
+        self.game
+    }
+
+    pub fn relocate(&mut self, game: FnafWorldGame) {
+        self.game = game;
+        self.disk.set(Seen::Unknown);
+        self.disk_keys.take();
+    }
+
+    /// Lets the next [`InfoData::save`] overwrite the file even if it was changed by another program
+    pub fn ignore_changes_on_disk(&self) {
+        self.disk.set(Seen::Unknown);
+        self.disk_keys.take();
+    }
+
     pub fn send_to_ui(&self, ui_weak: Weak<MainWindow>) {
         let ui = ui_weak.unwrap();
         let mut endings = vec![false; 7];
@@ -686,3 +864,45 @@ impl InfoData {
         ui.invoke_set_trophies(model, self.pearl, self.fan);
     }
 }
+
+/// Save parsed from the text of a save file, for tests
+#[cfg(test)]
+pub fn test_save(data: &str) -> SaveData {
+    SaveData::from(fnaf_world_parser(data).unwrap().into_iter().map(Token::from))
+}
+
+/// Trophies parsed from the text of an info file, for tests
+#[cfg(test)]
+pub fn test_info(data: &str) -> InfoData {
+    InfoData::from(fnaf_world_parser(data).unwrap().into_iter().map(Token::from))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn modded_ids_dont_panic() {
+        let save = test_save("[fnafw]\n0have=1\n60have=1\n60lv=3\nc0=1\nc30=1\np2=1\ns0=5\ns9=60\nactive1=30\ng0=1\nc5000000=1\n0have=1");
+        let characters = save.get_characters_ui();
+        assert_eq!(characters.len(), 60);
+        assert!(characters[59].activated && characters[59].lvl == 4);
+
+        let (chips, selected) = save.get_chips_ui();
+        assert_eq!((chips.len(), selected.len()), (30, 30));
+        assert!(chips[29] && selected[29] && !chips[0]);
+        assert_eq!(save.get_bytes_ui().0.len(), 21);
+        // Party places out of range are kept for saving
+        assert!(save.flags.contains(&Token::Slot { slot: 9, id: 60 }));
+        assert!(save.flags.contains(&Token::ClockFound(0)));
+    }
+
+    #[test]
+    fn relocate_rejects_wrong_slots() {
+        let mut save = test_save("[fnafw]\ntokens=5");
+        assert!(save.relocate(FnafWorldGame::WorldVanilla, 0).is_err());
+        assert!(save.relocate(FnafWorldGame::WorldVanilla, 4).is_err());
+        save.relocate(FnafWorldGame::WorldRefreshed, 4).unwrap();
+        assert_eq!((save.game(), save.slot()), (FnafWorldGame::WorldRefreshed, 4));
+    }
+}
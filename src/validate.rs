@@ -0,0 +1,80 @@
+//! Consistency checks of a save which the game itself doesn't do
+
+use derive_more::Display;
+use crate::armor;
+use crate::catalog::{self, Kind};
+use crate::tokenizer::SaveData;
+use crate::Game as FnafWorldGame;
+
+#[derive(Debug, Clone, PartialEq, Eq, Display)]
+pub enum Warning {
+    #[display("Party member {} (#{_0}) is not unlocked", catalog::name(Kind::Character, *_0))]
+    PartyNotOwned(u32),
+    #[display("Equipped chip {} (#{_0}) is not unlocked", catalog::name(Kind::Chip, *_0))]
+    ChipNotOwned(u32),
+    #[display("Equipped byte {} (#{_0}) is not unlocked", catalog::name(Kind::Byte, *_0))]
+    ByteNotOwned(u32),
+    #[display("Unknown {_0} #{_1}")]
+    Unknown(Kind, u32),
+    #[display("{} {} (#{_1}) exists only in FNaF World: Refreshed", _0, catalog::name(*_0, *_1))]
+    RefreshedOnly(Kind, u32),
+    #[display("Equipped armor (defence {_0}) is not bought{}", armor::by_defence(*_0).map(|p| format!(". It is {}", p.name)).unwrap_or_default())]
+    ArmorNotOwned(u32),
+    #[display("Unknown armor piece #{_0}")]
+    UnknownArmor(u32),
+}
+
+fn check_ids(warnings: &mut Vec<Warning>, game: FnafWorldGame, kind: Kind, ids: impl Iterator<Item = u32>) {
+    let mut ids = ids.collect::<Vec<_>>();
+    ids.sort_unstable();
+    for id in ids {
+        match catalog::get(kind, id) {
+            None => warnings.push(Warning::Unknown(kind, id)),
+            Some(e) if e.refreshed_only && game == FnafWorldGame::WorldVanilla => warnings.push(Warning::RefreshedOnly(kind, id)),
+            Some(_) => {},
+        }
+    }
+}
+
+/// Lists everything suspicious in the save. Empty slots of party and equipment (id 0) are fine
+pub fn validate(save: &SaveData) -> Vec<Warning> {
+    let mut warnings = Vec::new();
+    let equipped = |ids: &[u32]| ids.iter().copied().filter(|id| *id != 0).collect::<Vec<_>>();
+
+    warnings.extend(equipped(&save.selected_characters).into_iter().filter(|id| !save.characters.contains_key(id)).map(Warning::PartyNotOwned));
+    warnings.extend(equipped(&save.selected_chips).into_iter().filter(|id| !save.chips.contains(id)).map(Warning::ChipNotOwned));
+    warnings.extend(equipped(&save.selected_bytes).into_iter().filter(|id| !save.bytes.contains(id)).map(Warning::ByteNotOwned));
+
+    if !armor::is_consistent(&save.armor_owned, save.armor) {
+        warnings.push(Warning::ArmorNotOwned(save.armor));
+    }
+    let mut unknown_armor = save.armor_owned.iter().copied().filter(|id| armor::piece(*id).is_none()).collect::<Vec<_>>();
+    unknown_armor.sort_unstable();
+    warnings.extend(unknown_armor.into_iter().map(Warning::UnknownArmor));
+
+    check_ids(&mut warnings, save.game(), Kind::Character, save.characters.keys().copied());
+    check_ids(&mut warnings, save.game(), Kind::Chip, save.chips.iter().copied());
+    check_ids(&mut warnings, save.game(), Kind::Byte, save.bytes.iter().copied());
+    warnings
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tokenizer::test_save;
+
+    #[test]
+    fn reports_with_names() {
+        let data = "[fnafw]\n1have=1\n60have=1\nc1=1\nc23=1\ns1=2\nactive1=1\nactive1b=5\nar1=1\nar9=1\narmor=10";
+        let save = test_save(data);
+        let warnings = validate(&save);
+
+        assert!(warnings.contains(&Warning::PartyNotOwned(2)));
+        assert!(warnings.contains(&Warning::Unknown(Kind::Character, 60)));
+        assert!(warnings.contains(&Warning::RefreshedOnly(Kind::Chip, 23)));
+        assert!(warnings.iter().any(|w| w.to_string() == "Party member Bonnie (#2) is not unlocked"));
+        assert!(!warnings.iter().any(|w| matches!(w, Warning::ChipNotOwned(_))));
+        assert!(warnings.contains(&Warning::ArmorNotOwned(10)));
+        assert!(warnings.contains(&Warning::UnknownArmor(9)));
+    }
+}